@@ -1,9 +1,13 @@
 use clap::{Parser, Subcommand};
 use counter_app::{counter_key, extract_count, incremented_solution, CounterKey, QueryCount};
 use essential_app_utils::compile::compile_pint_project;
-use essential_rest_client::node_client::EssentialNodeClient;
+use essential_rest_client::{
+    builder_client::SolutionSetOutcome,
+    exit::{TimeoutError, ValidationError},
+    node_client::EssentialNodeClient,
+};
 use essential_types::{ContentAddress, PredicateAddress, SolutionSet};
-use std::path::PathBuf;
+use std::{path::PathBuf, process::ExitCode, time::Duration};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -27,14 +31,18 @@ enum Command {
         builder_api: String,
         /// The directory containing the pint files.
         pint_directory: PathBuf,
+        /// How long to wait for the solution set to be included in a block, in seconds.
+        #[arg(long, default_value_t = 30)]
+        timeout: u64,
     },
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let args = Cli::parse();
-    if let Err(err) = run(args).await {
-        eprintln!("Command failed because: {}", err);
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => essential_rest_client::exit::report(&err),
     }
 }
 
@@ -48,7 +56,7 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
             let address = compile_address(pint_directory).await?;
             let node = essential_rest_client::node_client::EssentialNodeClient::new(node_api)?;
             let key = counter_key();
-            let count = query_count(node, address.contract, key).await?;
+            let count = query_count(&node, address.contract, key).await?;
             let count_value = extract_count(count)?;
             println!("Current count is: {}", count_value);
         }
@@ -56,11 +64,12 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
             builder_api,
             node_api,
             pint_directory,
+            timeout,
         } => {
             let address = compile_address(pint_directory).await?;
             let node = essential_rest_client::node_client::EssentialNodeClient::new(node_api)?;
             let key = counter_key();
-            let count = query_count(node, address.contract.clone(), key).await?;
+            let count = query_count(&node, address.contract.clone(), key).await?;
             let (solution, new_count) = incremented_solution(address, count)?;
             let builder =
                 essential_rest_client::builder_client::EssentialBuilderClient::new(builder_api)?;
@@ -69,14 +78,30 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
             };
             let ca = builder.submit_solution_set(&solutions).await?;
             println!("Submitted solution: {}", ca);
-            println!("Incremented count to: {}", new_count);
+            let outcome = builder
+                .await_outcome(&node, &ca, Duration::from_secs(timeout))
+                .await?;
+            println!("Solution {}", outcome);
+            match outcome {
+                SolutionSetOutcome::Included { .. } => {
+                    println!("Incremented count to: {}", new_count)
+                }
+                SolutionSetOutcome::Failed(failure) => {
+                    let msg = format!("solution failed: {}", failure.err_msg);
+                    return Err(ValidationError(msg).into());
+                }
+                SolutionSetOutcome::TimedOut => {
+                    let msg = format!("no outcome for solution {ca} within {timeout}s");
+                    return Err(TimeoutError(msg).into());
+                }
+            }
         }
     }
     Ok(())
 }
 
 async fn query_count(
-    node: EssentialNodeClient,
+    node: &EssentialNodeClient,
     address: ContentAddress,
     key: CounterKey,
 ) -> anyhow::Result<QueryCount> {
@@ -1,22 +1,18 @@
-use essential_app_utils::compile::compile_pint_project;
+use essential_devnet::{Config, Devnet};
 use regex::Regex;
-use std::process::Stdio;
-use tokio::{
-    io::{AsyncBufReadExt, BufReader},
-    process::{Child, Command as TokioCommand},
-};
+use tokio::process::Command as TokioCommand;
 
 const PINT_DIRECTORY: &str = "../pint";
 
 #[tokio::test]
 async fn builder_integration() {
-    let (_builder_process, node_address, builder_address) = start_essential_builder().await;
-
-    let _ = compile_pint_project(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint").into())
-        .await
-        .unwrap();
-
-    deploy_contract(builder_address.clone()).await;
+    let config = Config {
+        deploy: vec![concat!(env!("CARGO_MANIFEST_DIR"), "/../pint").into()],
+        ..Default::default()
+    };
+    let devnet = Devnet::start(config).await.unwrap();
+    let node_address = devnet.node_address().to_string();
+    let builder_address = devnet.builder_address().to_string();
 
     let count = read_count(node_address.clone(), PINT_DIRECTORY).await;
     assert_eq!(count, 0);
@@ -46,29 +42,12 @@ async fn builder_integration() {
 }
 
 async fn read_count(node_address: String, pint_directory: &str) -> u32 {
-    let read_output = TokioCommand::new("cargo")
-        .args([
-            "run",
-            "--",
-            "read-count",
-            &format!("http://{}", node_address.as_str()),
-            pint_directory,
-        ])
-        .output()
-        .await
-        .expect("Failed to execute command");
-
-    assert!(read_output.status.success(), "Command failed to run");
-
-    let stdout_str = String::from_utf8(read_output.stdout).expect("Failed to parse stdout");
-
-    let count = stdout_str
-        .split_whitespace()
-        .last()
-        .expect("Failed to parse count")
-        .parse::<u32>()
-        .expect("Failed to parse count");
-    count
+    let stdout = run_app(&["read-count", &node_address, pint_directory]).await;
+    let regx_count = Regex::new(r"Current count is: (\d+)").unwrap();
+    let captures = regx_count
+        .captures(&stdout)
+        .unwrap_or_else(|| panic!("No count in the output of `read-count`:\n{stdout}"));
+    captures[1].parse::<u32>().expect("Failed to parse count")
 }
 
 async fn increment_count(
@@ -76,82 +55,39 @@ async fn increment_count(
     builder_address: String,
     pint_directory: &str,
 ) -> u32 {
-    let increment_output = TokioCommand::new("cargo")
-        .args([
-            "run",
-            "--",
-            "increment-count",
-            &format!("http://{}", node_address.as_str()),
-            &format!("http://{}", builder_address.as_str()),
-            pint_directory,
-        ])
-        .output()
-        .await
-        .expect("Failed to execute command");
-
-    // Read stdout
-    let stdout_str = String::from_utf8(increment_output.stdout).expect("Failed to parse stdout");
+    let stdout = run_app(&[
+        "increment-count",
+        &node_address,
+        &builder_address,
+        pint_directory,
+    ])
+    .await;
 
     // Regular expression to capture the new number
     let regx_new_count = Regex::new(r"Incremented count to: (\d+)").unwrap();
-    let mut new_count = 0;
-
-    // Check if stdout contains the desired line and capture the number
-    if let Some(captures) = regx_new_count.captures(&stdout_str) {
-        new_count = captures[1].parse::<u32>().expect("Failed to parse count");
-    }
-    new_count
+    let captures = regx_new_count
+        .captures(&stdout)
+        .unwrap_or_else(|| panic!("No new count in the output of `increment-count`:\n{stdout}"));
+    captures[1].parse::<u32>().expect("Failed to parse count")
 }
 
-async fn start_essential_builder() -> (Child, String, String) {
-    let mut builder_process = TokioCommand::new("essential-builder")
-        .args(["--block-interval-ms", "100"])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .kill_on_drop(true)
-        .spawn()
-        .expect("Failed to start essential-builder");
-
-    // Ensure the process is running
-    let stdout = builder_process
-        .stdout
-        .take()
-        .expect("Failed to capture stdout");
-    let mut reader = BufReader::new(stdout).lines();
-
-    // Regular expression to capture the address
-    let regx_node = Regex::new(r"Starting node API server at (.+)").unwrap();
-    let regx_builder = Regex::new(r"Starting builder API server at (.+)").unwrap();
-    let mut node_address = String::new();
-    let mut builder_address = String::new();
-
-    // Wait for the specific line in the builder output
-    while let Some(line) = reader.next_line().await.unwrap() {
-        println!("Builder output: {}", line);
-        if let Some(captures) = regx_node.captures(&line) {
-            node_address = captures[1].to_string();
-        }
-        if let Some(captures) = regx_builder.captures(&line) {
-            builder_address = captures[1].to_string();
-        }
-        if line.contains("Running the block builder") {
-            break;
-        }
-    }
-
-    (builder_process, node_address, builder_address)
-}
-
-async fn deploy_contract(builder_address: String) {
-    let deploy_output = TokioCommand::new("essential-rest-client")
-        .args([
-            "register-contract",
-            &format!("http://{}", builder_address.as_str()),
-            concat!(env!("CARGO_MANIFEST_DIR"), "/../pint/out/debug/pint.json"),
-        ])
+/// Run the counter app with the given arguments, returning its stdout.
+///
+/// Panics with the app's output if it fails.
+async fn run_app(args: &[&str]) -> String {
+    let output = TokioCommand::new("cargo")
+        .args(["run", "--"])
+        .args(args)
         .output()
         .await
         .expect("Failed to execute command");
-
-    assert!(deploy_output.status.success(), "Command failed to run");
+    let stdout = String::from_utf8(output.stdout).expect("Failed to parse stdout");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        output.status.success(),
+        "`{}` failed with {}:\n{stdout}\n{stderr}",
+        args.join(" "),
+        output.status
+    );
+    stdout
 }
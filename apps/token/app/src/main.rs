@@ -4,14 +4,16 @@ use anyhow::bail;
 use clap::{Args, Parser, Subcommand};
 use essential_app_utils::compile::compile_pint_project;
 use essential_rest_client::{
-    builder_client::EssentialBuilderClient, node_client::EssentialNodeClient,
+    builder_client::{EssentialBuilderClient, SolutionSetOutcome},
+    exit::{TimeoutError, ValidationError},
+    node_client::EssentialNodeClient,
 };
 use essential_signer::Signature;
 use essential_types::{
-    convert::word_4_from_u8_32, ContentAddress, PredicateAddress, SolutionSet, Word,
+    convert::word_4_from_u8_32, ContentAddress, Key, PredicateAddress, SolutionSet, Value, Word,
 };
 use essential_wallet::Wallet;
-use std::path::PathBuf;
+use std::{path::PathBuf, process::ExitCode, time::Duration};
 use token::Query;
 
 #[derive(Parser)]
@@ -21,6 +23,9 @@ struct Cli {
     /// If not set then a sensible default will be used (like ~/.essential-wallet).
     #[arg(short, long)]
     wallet: Option<PathBuf>,
+    /// How long to wait for a submitted solution set to be included in a block, in seconds.
+    #[arg(long, default_value_t = 30)]
+    timeout: u64,
     #[command(subcommand)]
     command: Command,
 }
@@ -105,15 +110,21 @@ enum Command {
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let args = Cli::parse();
-    if let Err(err) = run(args).await {
-        eprintln!("Command failed because: {}", err);
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => essential_rest_client::exit::report(&err),
     }
 }
 
 async fn run(cli: Cli) -> anyhow::Result<()> {
-    let Cli { wallet, command } = cli;
+    let Cli {
+        wallet,
+        timeout,
+        command,
+    } = cli;
+    let timeout = Duration::from_secs(timeout);
     let wallet = match &command {
         Command::ExternalBalance(_) => None,
         _ => {
@@ -132,14 +143,16 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
                 args.amount, args.account, args.token_name, args.token_symbol
             );
             let wallet = wallet.unwrap();
-            let addr = mint(wallet, args).await?;
+            let (addr, outcome) = mint(wallet, args, timeout).await?;
             println!("sent mint solution: {}", addr);
+            report_outcome(outcome)?;
         }
         Command::Burn(args) => {
             println!("burning {} for account: {}", args.amount, args.account);
             let wallet = wallet.unwrap();
-            let addr = burn(wallet, args).await?;
+            let (addr, outcome) = burn(wallet, args, timeout).await?;
             println!("sent burn solution: {}", addr);
+            report_outcome(outcome)?;
         }
         Command::Transfer(args) => {
             println!(
@@ -147,8 +160,9 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
                 args.amount, args.from_account, args.to_account
             );
             let wallet = wallet.unwrap();
-            let addr = transfer(wallet, args).await?;
+            let (addr, outcome) = transfer(wallet, args, timeout).await?;
             println!("sent transfer solution: {}", addr);
+            report_outcome(outcome)?;
         }
         Command::Balance(args) => {
             let Balance {
@@ -191,7 +205,11 @@ fn hash_key(wallet: &mut Wallet, account_name: &str) -> [Word; 4] {
     word_4_from_u8_32(essential_hash::hash_words(&encoded))
 }
 
-async fn mint(mut wallet: Wallet, args: Mint) -> anyhow::Result<ContentAddress> {
+async fn mint(
+    mut wallet: Wallet,
+    args: Mint,
+    timeout: Duration,
+) -> anyhow::Result<(ContentAddress, SolutionSetOutcome)> {
     let Mint {
         account,
         amount,
@@ -206,10 +224,8 @@ async fn mint(mut wallet: Wallet, args: Mint) -> anyhow::Result<ContentAddress>
     let node = EssentialNodeClient::new(node_api)?;
     let builder = EssentialBuilderClient::new(builder_api)?;
 
-    let nonce_key = token::nonce_key(hashed_key);
-    let nonce = node
-        .query_state(address.contract.clone(), nonce_key)
-        .await?;
+    let keys = [token::nonce_key(hashed_key), token::balance_key(hashed_key)];
+    let [nonce, balance] = query_states(&node, &address.contract, keys).await?;
     let init = token::mint::Init {
         hashed_key,
         amount,
@@ -221,10 +237,6 @@ async fn mint(mut wallet: Wallet, args: Mint) -> anyhow::Result<ContentAddress>
     let Signature::Secp256k1(sig) = sig else {
         bail!("Invalid signature")
     };
-    let balance_key = token::balance_key(hashed_key);
-    let balance = node
-        .query_state(address.contract.clone(), balance_key)
-        .await?;
     let build_solution = token::mint::BuildSolution {
         new_nonce: to_sign.new_nonce,
         current_balance: Query(balance),
@@ -239,11 +251,14 @@ async fn mint(mut wallet: Wallet, args: Mint) -> anyhow::Result<ContentAddress>
     let solution_set = SolutionSet {
         solutions: vec![solution],
     };
-    let ca = builder.submit_solution_set(&solution_set).await?;
-    Ok(ca)
+    submit_and_await(&node, &builder, &solution_set, timeout).await
 }
 
-async fn burn(mut wallet: Wallet, args: Burn) -> anyhow::Result<ContentAddress> {
+async fn burn(
+    mut wallet: Wallet,
+    args: Burn,
+    timeout: Duration,
+) -> anyhow::Result<(ContentAddress, SolutionSetOutcome)> {
     let Burn {
         account,
         amount,
@@ -256,10 +271,8 @@ async fn burn(mut wallet: Wallet, args: Burn) -> anyhow::Result<ContentAddress>
     let node = EssentialNodeClient::new(node_api)?;
     let builder = EssentialBuilderClient::new(builder_api)?;
 
-    let nonce_key = token::nonce_key(hashed_key);
-    let nonce = node
-        .query_state(address.contract.clone(), nonce_key)
-        .await?;
+    let keys = [token::nonce_key(hashed_key), token::balance_key(hashed_key)];
+    let [nonce, balance] = query_states(&node, &address.contract, keys).await?;
     let init = token::burn::Init {
         hashed_key,
         amount,
@@ -270,10 +283,6 @@ async fn burn(mut wallet: Wallet, args: Burn) -> anyhow::Result<ContentAddress>
     let Signature::Secp256k1(sig) = sig else {
         bail!("Invalid signature")
     };
-    let balance_key = token::balance_key(hashed_key);
-    let balance = node
-        .query_state(address.contract.clone(), balance_key)
-        .await?;
     let build_solution = token::burn::BuildSolution {
         new_nonce: to_sign.new_nonce,
         current_balance: Query(balance),
@@ -285,11 +294,14 @@ async fn burn(mut wallet: Wallet, args: Burn) -> anyhow::Result<ContentAddress>
     let solution_set = SolutionSet {
         solutions: vec![solution],
     };
-    let ca = builder.submit_solution_set(&solution_set).await?;
-    Ok(ca)
+    submit_and_await(&node, &builder, &solution_set, timeout).await
 }
 
-async fn transfer(mut wallet: Wallet, args: Transfer) -> anyhow::Result<ContentAddress> {
+async fn transfer(
+    mut wallet: Wallet,
+    args: Transfer,
+    timeout: Duration,
+) -> anyhow::Result<(ContentAddress, SolutionSetOutcome)> {
     let Transfer {
         amount,
         node_api,
@@ -308,10 +320,12 @@ async fn transfer(mut wallet: Wallet, args: Transfer) -> anyhow::Result<ContentA
     let node = EssentialNodeClient::new(node_api)?;
     let builder = EssentialBuilderClient::new(builder_api)?;
 
-    let nonce_key = token::nonce_key(hashed_from_key);
-    let nonce = node
-        .query_state(address.contract.clone(), nonce_key)
-        .await?;
+    let keys = [
+        token::nonce_key(hashed_from_key),
+        token::balance_key(hashed_from_key),
+        token::balance_key(hashed_to_key),
+    ];
+    let [nonce, from_balance, to_balance] = query_states(&node, &address.contract, keys).await?;
     let init = token::transfer::Init {
         amount,
         nonce: token::Query(nonce),
@@ -323,14 +337,6 @@ async fn transfer(mut wallet: Wallet, args: Transfer) -> anyhow::Result<ContentA
     let Signature::Secp256k1(sig) = sig else {
         bail!("Invalid signature")
     };
-    let balance_key = token::balance_key(hashed_from_key);
-    let from_balance = node
-        .query_state(address.contract.clone(), balance_key)
-        .await?;
-    let balance_key = token::balance_key(hashed_to_key);
-    let to_balance = node
-        .query_state(address.contract.clone(), balance_key)
-        .await?;
     let build_solution = token::transfer::BuildSolution {
         new_nonce: to_sign.new_nonce,
         current_from_balance: Query(from_balance),
@@ -344,8 +350,47 @@ async fn transfer(mut wallet: Wallet, args: Transfer) -> anyhow::Result<ContentA
     let solution_set = SolutionSet {
         solutions: vec![solution],
     };
-    let ca = builder.submit_solution_set(&solution_set).await?;
-    Ok(ca)
+    submit_and_await(&node, &builder, &solution_set, timeout).await
+}
+
+/// Queries the given keys of the token contract in a single batch.
+async fn query_states<const N: usize>(
+    node: &EssentialNodeClient,
+    contract: &ContentAddress,
+    keys: [Key; N],
+) -> anyhow::Result<[Option<Value>; N]> {
+    let values = node.query_states(contract.clone(), &keys).await?;
+    values
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Expected one value per key"))
+}
+
+/// Submits the solution set and waits for its outcome.
+async fn submit_and_await(
+    node: &EssentialNodeClient,
+    builder: &EssentialBuilderClient,
+    solution_set: &SolutionSet,
+    timeout: Duration,
+) -> anyhow::Result<(ContentAddress, SolutionSetOutcome)> {
+    let ca = builder.submit_solution_set(solution_set).await?;
+    let outcome = builder.await_outcome(node, &ca, timeout).await?;
+    Ok((ca, outcome))
+}
+
+/// Prints the outcome of a submitted solution set, failing if the solution set failed
+/// or timed out.
+fn report_outcome(outcome: SolutionSetOutcome) -> anyhow::Result<()> {
+    println!("solution {}", outcome);
+    match outcome {
+        SolutionSetOutcome::Included { .. } => Ok(()),
+        SolutionSetOutcome::Failed(failure) => {
+            let msg = format!("solution failed: {}", failure.err_msg);
+            Err(ValidationError(msg).into())
+        }
+        SolutionSetOutcome::TimedOut => {
+            Err(TimeoutError("no outcome for solution within the timeout".to_string()).into())
+        }
+    }
 }
 
 async fn get_balance(
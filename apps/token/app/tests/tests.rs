@@ -1,5 +1,4 @@
-use essential_app_utils::{self as utils, compile::compile_pint_project};
-use essential_node_types::BigBang;
+use essential_app_utils::{self as utils, test_chain::TestChain};
 use essential_signer::Signature;
 use essential_types::{convert::word_4_from_u8_32, solution::SolutionSet, Word};
 use essential_wallet::Wallet;
@@ -19,13 +18,6 @@ async fn mint_and_transfer() {
     // Initialize tracing for better debugging
     tracing_subscriber::fmt::init();
 
-    // Compile the token contract
-    // This requires `pint` be available on PATH
-    let (transfer, programs) =
-        compile_pint_project(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint/token").into())
-            .await
-            .unwrap();
-
     // Create a temporary wallet for testing
     let mut wallet = essential_wallet::Wallet::temp().unwrap();
 
@@ -45,26 +37,18 @@ async fn mint_and_transfer() {
     let first_mint_amount = 1000000;
     let alice_hashed_key = hash_key(&mut wallet, alice);
 
-    // Create new databases for testing
-    let dbs = utils::db::new_dbs().await;
-    let big_bang = BigBang::default();
-
-    // Deploy the token contract
-    let contract_registry = big_bang.contract_registry;
-    let program_registry = big_bang.program_registry;
-    essential_app_utils::deploy::register_contract_and_programs(
-        &dbs.builder,
-        &contract_registry,
-        &program_registry,
-        &transfer,
-        programs,
-    )
-    .await
-    .unwrap();
+    // Create a new chain for testing and deploy the token contract
+    // This requires `pint` be available on PATH
+    let mut chain = TestChain::new().await;
+    chain
+        .deploy(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint/token"))
+        .await
+        .unwrap();
 
     // Get Alice's nonce key
     let alice_nonce_key = token::nonce_key(alice_hashed_key);
-    let nonce = utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &alice_nonce_key)
+    let nonce = chain
+        .query(&token::token::ADDRESS, &alice_nonce_key)
         .await
         .unwrap();
 
@@ -83,10 +67,10 @@ async fn mint_and_transfer() {
 
     // Get Alice's balance key
     let alice_balance_key = token::balance_key(alice_hashed_key);
-    let balance =
-        utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &alice_balance_key)
-            .await
-            .unwrap();
+    let balance = chain
+        .query(&token::token::ADDRESS, &alice_balance_key)
+        .await
+        .unwrap();
 
     // Build the mint solution
     let build_solution = token::mint::BuildSolution {
@@ -105,25 +89,22 @@ async fn mint_and_transfer() {
         solutions: vec![solution],
     };
 
-    // Submit the mint solution
-    utils::builder::submit(&dbs.builder, solution_set.clone())
-        .await
-        .unwrap();
-
     // Validate the mint solution
-    utils::node::validate_solution(&dbs.node, solution_set.clone())
+    let dbs = chain.dbs();
+    utils::node::validate_solution(&dbs.node, &dbs.big_bang, solution_set.clone())
         .await
         .unwrap();
 
-    // Build a block
-    let o = utils::builder::build_default(&dbs).await.unwrap();
-    assert!(o.failed.is_empty(), "{:?}", o.failed);
+    // Submit the mint solution and build a block
+    let ca = chain.submit(solution_set).await.unwrap();
+    let o = chain.build_block().await.unwrap();
+    assert!(o.is_included(&ca), "{:?}", o.failed);
 
     // Verify Alice's balance after minting
-    let balance =
-        utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &alice_balance_key)
-            .await
-            .unwrap();
+    let balance = chain
+        .query(&token::token::ADDRESS, &alice_balance_key)
+        .await
+        .unwrap();
     assert_eq!(token::balance(Query(balance)).unwrap(), first_mint_amount);
 
     // Set up Bob's account
@@ -136,7 +117,8 @@ async fn mint_and_transfer() {
     let bob_hashed_key = hash_key(&mut wallet, bob);
 
     // Prepare the transfer solution
-    let nonce = utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &alice_nonce_key)
+    let nonce = chain
+        .query(&token::token::ADDRESS, &alice_nonce_key)
         .await
         .unwrap();
     let init = token::transfer::Init {
@@ -154,16 +136,16 @@ async fn mint_and_transfer() {
     };
 
     // Get current balances for Alice and Bob
-    let from_balance =
-        utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &alice_balance_key)
-            .await
-            .unwrap();
+    let from_balance = chain
+        .query(&token::token::ADDRESS, &alice_balance_key)
+        .await
+        .unwrap();
 
     let bob_balance_key = token::balance_key(bob_hashed_key);
-    let to_balance =
-        utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &bob_balance_key)
-            .await
-            .unwrap();
+    let to_balance = chain
+        .query(&token::token::ADDRESS, &bob_balance_key)
+        .await
+        .unwrap();
 
     // Build the transfer solution
     let solution = token::transfer::BuildSolution {
@@ -180,33 +162,32 @@ async fn mint_and_transfer() {
         solutions: vec![solution],
     };
 
-    // Submit the transfer solution
-    utils::builder::submit(&dbs.builder, solution_set.clone())
-        .await
-        .unwrap();
-
     // Validate the transfer solution
-    utils::node::validate_solution(&dbs.node, solution_set.clone())
+    let dbs = chain.dbs();
+    utils::node::validate_solution(&dbs.node, &dbs.big_bang, solution_set.clone())
         .await
         .unwrap();
-    let o = utils::builder::build_default(&dbs).await.unwrap();
-    assert!(o.failed.is_empty(), "{:?}", o.failed);
+
+    // Submit the transfer solution and build a block
+    let ca = chain.submit(solution_set).await.unwrap();
+    let o = chain.build_block().await.unwrap();
+    assert!(o.is_included(&ca), "{:?}", o.failed);
 
     // Verify Alice's balance after transfer
-    let balance =
-        utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &alice_balance_key)
-            .await
-            .unwrap();
+    let balance = chain
+        .query(&token::token::ADDRESS, &alice_balance_key)
+        .await
+        .unwrap();
     assert_eq!(
         token::balance(Query(balance)).unwrap(),
         first_mint_amount - 500
     );
 
     // Verify Bob's balance after transfer
-    let balance =
-        utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &bob_balance_key)
-            .await
-            .unwrap();
+    let balance = chain
+        .query(&token::token::ADDRESS, &bob_balance_key)
+        .await
+        .unwrap();
 
     assert_eq!(token::balance(Query(balance)).unwrap(), 500);
 }
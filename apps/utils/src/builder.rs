@@ -4,7 +4,13 @@ use essential_types::{solution::SolutionSet, ContentAddress};
 use crate::db::Dbs;
 
 pub async fn build_default(dbs: &Dbs) -> Result<SolutionSetsSummary, BuildBlockError> {
-    essential_builder::build_block_fifo(&dbs.builder, &dbs.node, &Default::default())
+    let config = essential_builder::Config {
+        contract_registry: dbs.big_bang.contract_registry.clone(),
+        program_registry: dbs.big_bang.program_registry.clone(),
+        block_state: dbs.big_bang.block_state.clone(),
+        ..Default::default()
+    };
+    essential_builder::build_block_fifo(&dbs.builder, &dbs.node, &config)
         .await
         .map(|(_, o)| o)
 }
@@ -1,29 +1,38 @@
 use essential_builder_db as builder_db;
 use essential_node as node;
+use essential_node_types::BigBang;
 
+#[derive(Clone)]
 pub struct Dbs {
     pub builder: builder_db::ConnectionPool,
     pub node: node::db::ConnectionPool,
+    pub big_bang: BigBang,
 }
 
 pub async fn new_dbs() -> Dbs {
+    new_dbs_with_big_bang(BigBang::default()).await
+}
+
+pub async fn new_dbs_with_big_bang(big_bang: BigBang) -> Dbs {
     let config = node::db::pool::Config {
         source: node::db::pool::Source::Memory(uuid::Uuid::new_v4().to_string()),
         ..Default::default()
     };
     let node = node::db::ConnectionPool::with_tables(&config).unwrap();
-    init_node_db(&node).await.unwrap();
+    init_node_db(&node, &big_bang).await.unwrap();
     let config = builder_db::pool::Config {
         source: builder_db::pool::Source::Memory(uuid::Uuid::new_v4().to_string()),
         ..Default::default()
     };
     let builder = builder_db::ConnectionPool::with_tables(&config).unwrap();
-    Dbs { builder, node }
+    Dbs {
+        builder,
+        node,
+        big_bang,
+    }
 }
 
-pub async fn init_node_db(db: &node::db::ConnectionPool) -> anyhow::Result<()> {
-    let big_bang = essential_node_types::BigBang::default();
-
-    essential_node::ensure_big_bang_block(db, &big_bang).await?;
+pub async fn init_node_db(db: &node::db::ConnectionPool, big_bang: &BigBang) -> anyhow::Result<()> {
+    essential_node::ensure_big_bang_block(db, big_bang).await?;
     Ok(())
 }
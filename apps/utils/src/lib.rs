@@ -7,3 +7,5 @@ pub mod inputs;
 pub mod node;
 pub mod print;
 pub mod read;
+#[cfg(feature = "test-utils")]
+pub mod test_chain;
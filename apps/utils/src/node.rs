@@ -1,33 +1,51 @@
-use essential_types::{ContentAddress, Key, Value};
+use essential_types::{ContentAddress, Key, Value, Word};
 
 pub async fn query_state_head(
     conn: &essential_node::db::ConnectionPool,
     address: &ContentAddress,
     key: &Key,
+) -> anyhow::Result<Option<Value>> {
+    let num = {
+        let mut c = conn.acquire().await?;
+        let tx = c.transaction()?;
+        let ca = essential_node_db::get_latest_finalized_block_address(&tx)?;
+        match ca {
+            Some(ca) => essential_node_db::get_block_header(&tx, &ca)?
+                .map(|h| h.number)
+                .unwrap_or_default(),
+            None => 0,
+        }
+    };
+    query_state_at(conn, address, key, num).await
+}
+
+/// Query the value of the key as of the end of the given block.
+///
+/// The node database has no index on mutation keys, so this searches the mutations of
+/// every finalized block up to `block_number`. The cost grows with the length of the
+/// chain, which is fine for tests but slow against a node with a long history.
+pub async fn query_state_at(
+    conn: &essential_node::db::ConnectionPool,
+    address: &ContentAddress,
+    key: &Key,
+    block_number: Word,
 ) -> anyhow::Result<Option<Value>> {
     let mut c = conn.acquire().await?;
     let tx = c.transaction()?;
-    let ca = essential_node_db::get_latest_finalized_block_address(&tx)?;
-    let num = match ca {
-        Some(ca) => essential_node_db::get_block_header(&tx, &ca)?
-            .map(|h| h.number)
-            .unwrap_or_default(),
-        None => 0,
-    };
-    let r = essential_node_db::finalized::query_state_inclusive_block(&tx, address, key, num)?;
+    let r =
+        essential_node_db::finalized::query_state_inclusive_block(&tx, address, key, block_number)?;
     Ok(r)
 }
 
 pub async fn validate_solution(
     conn: &essential_node::db::ConnectionPool,
+    big_bang: &essential_node_types::BigBang,
     solution_set: essential_types::solution::SolutionSet,
 ) -> anyhow::Result<()> {
-    let contract_registry_predicate = essential_node_types::BigBang::default().contract_registry;
-    let program_registry_predicate = essential_node_types::BigBang::default().program_registry;
     essential_node::validate_solution_set_dry_run(
         conn,
-        &contract_registry_predicate.contract,
-        &program_registry_predicate.contract,
+        &big_bang.contract_registry.contract,
+        &big_bang.program_registry.contract,
         solution_set,
     )
     .await?;
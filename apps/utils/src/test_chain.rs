@@ -0,0 +1,136 @@
+use crate::{
+    builder::{build_default, submit},
+    compile::compile_pint_project,
+    db::{new_dbs, new_dbs_with_big_bang, Dbs},
+    deploy::register_contract_and_programs,
+    node::query_state_head,
+};
+use anyhow::bail;
+use essential_node_types::BigBang;
+use essential_types::{contract::Contract, solution::SolutionSet, ContentAddress, Key, Value};
+use std::{collections::HashMap, path::PathBuf};
+
+/// An in-memory node and builder for testing apps without any APIs or external processes.
+///
+/// Blocks are only built when [`TestChain::build_block`] is called.
+pub struct TestChain {
+    dbs: Dbs,
+    /// The number of submissions of each solution set not yet attempted in a block.
+    ///
+    /// The builder attempts each submission, so a set submitted twice has two outcomes.
+    pending: HashMap<ContentAddress, usize>,
+}
+
+/// The result of building a block with [`TestChain::build_block`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BlockOutcome {
+    /// The submitted solution sets included in the block, in block order.
+    pub included: Vec<ContentAddress>,
+    /// The submitted solution sets that failed, with the reason for each failure.
+    pub failed: Vec<(ContentAddress, String)>,
+}
+
+impl TestChain {
+    /// Create a chain with the default big bang configuration.
+    pub async fn new() -> Self {
+        Self::from_dbs(new_dbs().await)
+    }
+
+    /// Create a chain with the given big bang configuration.
+    pub async fn with_big_bang(big_bang: BigBang) -> Self {
+        Self::from_dbs(new_dbs_with_big_bang(big_bang).await)
+    }
+
+    fn from_dbs(dbs: Dbs) -> Self {
+        Self {
+            dbs,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// The node and builder databases backing the chain.
+    pub fn dbs(&self) -> &Dbs {
+        &self.dbs
+    }
+
+    /// Build the pint project in the given directory and register its contract and
+    /// programs in a new block.
+    ///
+    /// This requires `pint` be available on `PATH`.
+    pub async fn deploy(&mut self, path: impl Into<PathBuf>) -> anyhow::Result<Contract> {
+        let (contract, programs) = compile_pint_project(path.into()).await?;
+        let ca = register_contract_and_programs(
+            &self.dbs.builder,
+            &self.dbs.big_bang.contract_registry,
+            &self.dbs.big_bang.program_registry,
+            &contract,
+            programs,
+        )
+        .await?;
+        self.add_pending(&ca);
+        let outcome = self.build_block().await?;
+        if let Some((_, reason)) = outcome.failed.iter().find(|(failed, _)| *failed == ca) {
+            bail!("Failed to register contract: {reason}");
+        }
+        Ok(contract)
+    }
+
+    /// Submit the solution set to the builder, to be attempted in the next block.
+    pub async fn submit(&mut self, solution_set: SolutionSet) -> anyhow::Result<ContentAddress> {
+        let ca = submit(&self.dbs.builder, solution_set).await?;
+        self.add_pending(&ca);
+        Ok(ca)
+    }
+
+    /// Build a block from the submitted solution sets in the order they were submitted.
+    pub async fn build_block(&mut self) -> anyhow::Result<BlockOutcome> {
+        let summary = build_default(&self.dbs).await?;
+        // Only report the submitted sets, not those the builder adds to each block.
+        let included = summary
+            .succeeded
+            .into_iter()
+            .map(|(ca, _)| ca)
+            .filter(|ca| self.take_pending(ca))
+            .collect();
+        let failed = summary
+            .failed
+            .into_iter()
+            .filter(|(ca, _, _)| self.take_pending(ca))
+            .map(|(ca, _, invalid)| (ca, invalid.to_string()))
+            .collect();
+        Ok(BlockOutcome { included, failed })
+    }
+
+    /// Record a submission of the given solution set.
+    fn add_pending(&mut self, ca: &ContentAddress) {
+        *self.pending.entry(ca.clone()).or_default() += 1;
+    }
+
+    /// Take one pending submission of the given solution set, returning whether there was one.
+    fn take_pending(&mut self, ca: &ContentAddress) -> bool {
+        let Some(count) = self.pending.get_mut(ca) else {
+            return false;
+        };
+        *count -= 1;
+        if *count == 0 {
+            self.pending.remove(ca);
+        }
+        true
+    }
+
+    /// Query the state of the given contract at the given key as of the latest block.
+    pub async fn query(
+        &self,
+        contract: &ContentAddress,
+        key: &Key,
+    ) -> anyhow::Result<Option<Value>> {
+        query_state_head(&self.dbs.node, contract, key).await
+    }
+}
+
+impl BlockOutcome {
+    /// Whether the given solution set was included in the block.
+    pub fn is_included(&self, solution_set: &ContentAddress) -> bool {
+        self.included.contains(solution_set)
+    }
+}
@@ -0,0 +1,204 @@
+//! A local, in-memory Essential node and builder.
+//!
+//! [`Devnet::start`] serves the node and builder APIs from in-memory databases and builds
+//! blocks from submitted solution sets at a fixed interval, without any external processes.
+
+#![deny(missing_docs)]
+
+use essential_app_utils::{
+    builder::build_default,
+    compile::compile_pint_project,
+    db::{new_dbs_with_big_bang, Dbs},
+    deploy::register_contract_and_programs,
+};
+use essential_node_types::{block_notify::BlockTx, BigBang};
+use essential_types::ContentAddress;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    net::{Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    time::Duration,
+};
+use tokio::{net::TcpListener, task::JoinHandle};
+
+/// Configuration for a [`Devnet`].
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The local port to serve the node API on, or `0` for any free port.
+    pub node_port: u16,
+    /// The local port to serve the builder API on, or `0` for any free port.
+    pub builder_port: u16,
+    /// How often to build a block from the submitted solution sets. Must be non-zero.
+    pub block_interval: Duration,
+    /// The big bang configuration of the chain.
+    pub big_bang: BigBang,
+    /// Directories of pint projects to build and register before serving.
+    pub deploy: Vec<PathBuf>,
+}
+
+/// A running node and builder, serving their APIs on localhost.
+///
+/// The APIs and block building stop when the devnet is dropped.
+pub struct Devnet {
+    dbs: Dbs,
+    endpoints: Endpoints,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+/// The addresses served by a [`Devnet`] and the contracts registered at start up.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Endpoints {
+    /// The URL of the node API.
+    pub node_address: String,
+    /// The URL of the builder API.
+    pub builder_address: String,
+    /// The address of each pre-deployed contract, by project directory.
+    pub contracts: BTreeMap<PathBuf, ContentAddress>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            node_port: 0,
+            builder_port: 0,
+            block_interval: Duration::from_millis(100),
+            big_bang: BigBang::default(),
+            deploy: vec![],
+        }
+    }
+}
+
+impl Devnet {
+    /// Start serving a new chain with the given configuration.
+    ///
+    /// Projects to deploy are built with `pint` and registered in the first block, before
+    /// the APIs start serving.
+    pub async fn start(config: Config) -> anyhow::Result<Self> {
+        let Config {
+            node_port,
+            builder_port,
+            block_interval,
+            big_bang,
+            deploy,
+        } = config;
+        anyhow::ensure!(
+            !block_interval.is_zero(),
+            "The block interval must be non-zero"
+        );
+        let dbs = new_dbs_with_big_bang(big_bang).await;
+
+        let mut contracts = BTreeMap::new();
+        for path in deploy {
+            let (contract, programs) = compile_pint_project(path.clone()).await?;
+            register_contract_and_programs(
+                &dbs.builder,
+                &dbs.big_bang.contract_registry,
+                &dbs.big_bang.program_registry,
+                &contract,
+                programs,
+            )
+            .await?;
+            contracts.insert(path, essential_hash::content_addr(&contract));
+        }
+        if !contracts.is_empty() {
+            let summary = build_default(&dbs).await?;
+            if let Some((_, _, err)) = summary.failed.first() {
+                anyhow::bail!("Failed to register the contracts to deploy: {err}");
+            }
+        }
+
+        let block_tx = BlockTx::new();
+        let node_listener = listener(node_port).await?;
+        let builder_listener = listener(builder_port).await?;
+        let endpoints = Endpoints {
+            node_address: format!("http://{}", node_listener.local_addr()?),
+            builder_address: format!("http://{}", builder_listener.local_addr()?),
+            contracts,
+        };
+
+        let node_router = essential_node_api::router(essential_node_api::State {
+            conn_pool: dbs.node.clone(),
+            new_block: Some(block_tx.new_listener()),
+        });
+        let builder_router = essential_builder_api::router(essential_builder_api::State {
+            conn_pool: dbs.builder.clone(),
+        });
+        let tasks = vec![
+            tokio::spawn(async move {
+                essential_node_api::serve(
+                    &node_router,
+                    &node_listener,
+                    essential_node_api::DEFAULT_CONNECTION_LIMIT,
+                )
+                .await
+            }),
+            tokio::spawn(async move {
+                essential_builder_api::serve(
+                    &builder_router,
+                    &builder_listener,
+                    essential_builder_api::DEFAULT_CONNECTION_LIMIT,
+                )
+                .await
+            }),
+            tokio::spawn(build_blocks(dbs.clone(), block_interval, block_tx)),
+        ];
+
+        Ok(Self {
+            dbs,
+            endpoints,
+            tasks,
+        })
+    }
+
+    /// The addresses served by the devnet.
+    pub fn endpoints(&self) -> &Endpoints {
+        &self.endpoints
+    }
+
+    /// The URL of the node API.
+    pub fn node_address(&self) -> &str {
+        &self.endpoints.node_address
+    }
+
+    /// The URL of the builder API.
+    pub fn builder_address(&self) -> &str {
+        &self.endpoints.builder_address
+    }
+
+    /// The node and builder databases, for inspecting or seeding state directly.
+    pub fn dbs(&self) -> &Dbs {
+        &self.dbs
+    }
+}
+
+impl Drop for Devnet {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Bind a listener to the given local port.
+async fn listener(port: u16) -> anyhow::Result<TcpListener> {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let listener = TcpListener::bind(addr).await?;
+    Ok(listener)
+}
+
+/// Build a block from the submitted solution sets every interval, notifying the node API's
+/// block subscribers of each new block.
+async fn build_blocks(dbs: Dbs, interval: Duration, block_tx: BlockTx) {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        match build_default(&dbs).await {
+            // The block state solution set always succeeds, and a block is only committed
+            // if a submitted solution set succeeded too.
+            Ok(summary) if summary.succeeded.len() > 1 => block_tx.notify(),
+            Ok(_) => (),
+            Err(err) => tracing::error!("Failed to build block: {err}"),
+        }
+    }
+}
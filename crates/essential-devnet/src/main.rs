@@ -0,0 +1,65 @@
+use clap::Parser;
+use essential_devnet::{Config, Devnet};
+use essential_rest_client::{big_bang_or_default, exit};
+use std::{path::PathBuf, process::ExitCode, time::Duration};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+/// Run a local, in-memory Essential node and builder.
+///
+/// Once serving, the node and builder endpoints are printed as JSON.
+struct Args {
+    /// The local port to serve the node API on. `0` picks any free port.
+    #[arg(long, default_value_t = 3553)]
+    node_port: u16,
+    /// The local port to serve the builder API on. `0` picks any free port.
+    #[arg(long, default_value_t = 3554)]
+    builder_port: u16,
+    /// How often to build a block from the submitted solution sets, in milliseconds.
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u64).range(1..))]
+    block_interval_ms: u64,
+    /// Path to the big bang configuration of the chain as YAML.
+    ///
+    /// If not specified, the default big bang configuration is used.
+    #[arg(long)]
+    big_bang: Option<PathBuf>,
+    /// The directory of a pint project to build and register before serving.
+    ///
+    /// May be given multiple times.
+    #[arg(long)]
+    deploy: Vec<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
+    let args = Args::parse();
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => exit::report(&err),
+    }
+}
+
+async fn run(args: Args) -> anyhow::Result<()> {
+    let Args {
+        node_port,
+        builder_port,
+        block_interval_ms,
+        big_bang,
+        deploy,
+    } = args;
+    let big_bang = big_bang_or_default(big_bang.as_deref()).await?;
+    let config = Config {
+        node_port,
+        builder_port,
+        block_interval: Duration::from_millis(block_interval_ms),
+        big_bang,
+        deploy,
+    };
+    let devnet = Devnet::start(config).await?;
+    println!("{}", serde_json::to_string(devnet.endpoints())?);
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}
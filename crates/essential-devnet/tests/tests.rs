@@ -0,0 +1,149 @@
+use essential_devnet::{Config, Devnet};
+use essential_rest_client::{
+    builder_client::{EssentialBuilderClient, SolutionSetOutcome},
+    config::ClientConfig,
+    node_client::EssentialNodeClient,
+};
+use essential_types::{
+    contract::Contract,
+    solution::{Mutation, Solution, SolutionSet},
+    ContentAddress, PredicateAddress,
+};
+use std::time::Duration;
+
+/// Clients for the given devnet that poll often, to keep the tests fast.
+fn clients(devnet: &Devnet) -> (EssentialNodeClient, EssentialBuilderClient) {
+    let config = ClientConfig::builder()
+        .poll_interval(Duration::from_millis(20))
+        .build();
+    let node = EssentialNodeClient::with_config(devnet.node_address().to_string(), config.clone())
+        .unwrap();
+    let builder =
+        EssentialBuilderClient::with_config(devnet.builder_address().to_string(), config).unwrap();
+    (node, builder)
+}
+
+#[tokio::test]
+async fn test_ping_endpoints() {
+    let devnet = Devnet::start(Config::default()).await.unwrap();
+
+    let node = EssentialNodeClient::new(devnet.node_address().to_string()).unwrap();
+    let info = node.info().await.unwrap();
+    assert!(info.uses_big_bang(&Config::default().big_bang));
+    let builder = EssentialBuilderClient::new(devnet.builder_address().to_string()).unwrap();
+    builder.info().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_zero_block_interval() {
+    let config = Config {
+        block_interval: Duration::ZERO,
+        ..Default::default()
+    };
+    assert!(Devnet::start(config).await.is_err());
+}
+
+#[tokio::test]
+async fn test_await_outcome() {
+    let devnet = Devnet::start(Config::default()).await.unwrap();
+    let big_bang = Config::default().big_bang;
+    let (node, builder) = clients(&devnet);
+    let timeout = Duration::from_secs(10);
+
+    // A contract registration is included in a block.
+    let contract = Contract {
+        predicates: vec![],
+        salt: [1u8; 32],
+    };
+    let ca = builder
+        .register_contract(
+            &big_bang.contract_registry,
+            &big_bang.program_registry,
+            &contract,
+            &[],
+        )
+        .await
+        .unwrap();
+    let outcome = builder.await_outcome(&node, &ca, timeout).await.unwrap();
+    assert!(
+        matches!(outcome, SolutionSetOutcome::Included { .. }),
+        "{outcome}"
+    );
+
+    // Solving a predicate that was never registered fails.
+    let solution_set = SolutionSet {
+        solutions: vec![Solution {
+            predicate_to_solve: PredicateAddress {
+                contract: ContentAddress([42u8; 32]),
+                predicate: ContentAddress([42u8; 32]),
+            },
+            predicate_data: vec![],
+            state_mutations: vec![],
+        }],
+    };
+    let ca = builder.submit_solution_set(&solution_set).await.unwrap();
+    let outcome = builder.await_outcome(&node, &ca, timeout).await.unwrap();
+    assert!(
+        matches!(outcome, SolutionSetOutcome::Failed(_)),
+        "{outcome}"
+    );
+
+    // A solution set that was never submitted has no outcome.
+    let outcome = builder
+        .await_outcome(
+            &node,
+            &ContentAddress([0u8; 32]),
+            Duration::from_millis(200),
+        )
+        .await
+        .unwrap();
+    assert_eq!(outcome, SolutionSetOutcome::TimedOut);
+}
+
+#[tokio::test]
+async fn test_submit_and_query_state() {
+    let counter = concat!(env!("CARGO_MANIFEST_DIR"), "/../../apps/counter/pint");
+    let config = Config {
+        deploy: vec![counter.into()],
+        ..Default::default()
+    };
+    let devnet = Devnet::start(config).await.unwrap();
+    let (node, builder) = clients(&devnet);
+
+    let (contract, _) = essential_app_utils::compile::compile_pint_project(counter.into())
+        .await
+        .unwrap();
+    let address = PredicateAddress {
+        contract: essential_hash::content_addr(&contract),
+        predicate: essential_hash::content_addr(&contract.predicates[0]),
+    };
+    assert_eq!(
+        devnet.endpoints().contracts.values().next(),
+        Some(&address.contract)
+    );
+
+    // Increment the counter from nothing to one.
+    let key = vec![0];
+    let solution_set = SolutionSet {
+        solutions: vec![Solution {
+            predicate_to_solve: address.clone(),
+            predicate_data: vec![],
+            state_mutations: vec![Mutation {
+                key: key.clone(),
+                value: vec![1],
+            }],
+        }],
+    };
+    let ca = builder.submit_solution_set(&solution_set).await.unwrap();
+    let outcome = builder
+        .await_outcome(&node, &ca, Duration::from_secs(10))
+        .await
+        .unwrap();
+    assert!(
+        matches!(outcome, SolutionSetOutcome::Included { .. }),
+        "{outcome}"
+    );
+
+    let state = node.query_state(address.contract, key).await.unwrap();
+    assert_eq!(state, Some(vec![1]));
+}
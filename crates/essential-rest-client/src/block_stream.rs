@@ -0,0 +1,234 @@
+//! The block subscription stream behind [`EssentialNodeClient::subscribe_blocks`].
+
+use crate::{
+    error::ClientError,
+    node_client::{EssentialNodeClient, BLOCK_PAGE_SIZE},
+    send_with_timeout,
+};
+use essential_node_types::Block;
+use essential_types::Word;
+use reqwest::StatusCode;
+use std::collections::VecDeque;
+
+/// Tracks the cursor and connection state of a block subscription.
+pub(crate) struct BlockSubscription {
+    node: EssentialNodeClient,
+    /// The number of the next block to yield.
+    next: Word,
+    mode: Mode,
+    /// Decodes the server-sent events received from the stream.
+    events: EventDecoder,
+    /// Blocks that have been received but not yet yielded.
+    pending: VecDeque<Block>,
+}
+
+/// How the subscription is currently receiving blocks.
+enum Mode {
+    /// Not connected, the streaming endpoint should be (re)tried.
+    Connect,
+    /// Receiving server-sent events from the streaming endpoint.
+    Stream(reqwest::Response),
+    /// The node has no streaming endpoint, so `list-blocks` is polled instead.
+    Poll,
+}
+
+impl BlockSubscription {
+    /// Start a subscription at the given block number.
+    pub(crate) fn new(node: EssentialNodeClient, start: Word) -> Self {
+        Self {
+            node,
+            next: start,
+            mode: Mode::Connect,
+            events: EventDecoder::default(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Produce the next block in the subscription.
+    ///
+    /// Errors leave the subscription in a state where the following call resumes
+    /// from the next expected block.
+    pub(crate) async fn next_block(&mut self) -> Result<Block, ClientError> {
+        loop {
+            if let Some(block) = self.pending.pop_front() {
+                self.next = block.header.number.saturating_add(1);
+                return Ok(block);
+            }
+            match &mut self.mode {
+                Mode::Connect => self.connect().await?,
+                Mode::Stream(response) => match response.chunk().await {
+                    Ok(Some(chunk)) => {
+                        self.events.push(&chunk);
+                        self.decode_events()?;
+                    }
+                    // The server closed the stream, reconnect from the cursor.
+                    Ok(None) => self.reconnect(),
+                    Err(err) => {
+                        self.reconnect();
+                        return Err(err.into());
+                    }
+                },
+                Mode::Poll => {
+                    let range = self.next..self.next.saturating_add(BLOCK_PAGE_SIZE);
+                    let blocks = self.node.list_blocks(range).await?;
+                    if blocks.is_empty() {
+                        tokio::time::sleep(self.node.config().poll_interval()).await;
+                    }
+                    self.extend_pending(blocks);
+                }
+            }
+        }
+    }
+
+    /// Connect to the node's streaming endpoint, falling back to polling if it is missing.
+    async fn connect(&mut self) -> Result<(), ClientError> {
+        let mut url = self.node.url().join("/subscribe-blocks")?;
+        url.query_pairs_mut()
+            .append_pair("start_block", &self.next.to_string());
+        let request = self.node.client().get(url);
+        match send_with_timeout(self.node.config(), request, None).await {
+            Ok(response) => self.mode = Mode::Stream(response),
+            Err(err) if err.status() == Some(StatusCode::NOT_FOUND) => self.mode = Mode::Poll,
+            Err(err) => return Err(err),
+        }
+        Ok(())
+    }
+
+    /// Drop the current stream so that the next call reconnects.
+    fn reconnect(&mut self) {
+        self.mode = Mode::Connect;
+        self.events = EventDecoder::default();
+    }
+
+    /// Decode all complete events received so far into pending blocks.
+    fn decode_events(&mut self) -> Result<(), ClientError> {
+        let mut blocks = vec![];
+        while let Some(data) = self.events.next_event() {
+            match serde_json::from_slice(&data) {
+                Ok(block) => blocks.push(block),
+                Err(err) => {
+                    self.extend_pending(blocks);
+                    self.reconnect();
+                    return Err(ClientError::Decode(err));
+                }
+            }
+        }
+        self.extend_pending(blocks);
+        Ok(())
+    }
+
+    /// Queue the given blocks, skipping any that precede the cursor.
+    fn extend_pending(&mut self, blocks: Vec<Block>) {
+        let next = self
+            .pending
+            .back()
+            .map_or(self.next, |b| b.header.number.saturating_add(1));
+        self.pending
+            .extend(blocks.into_iter().filter(|b| b.header.number >= next));
+    }
+}
+
+/// Splits a stream of bytes into the data of server-sent events.
+///
+/// Lines may end in `\r\n`, `\n` or `\r`, and events are separated by a blank line.
+/// Only `data` fields are kept. Comments (e.g. keep-alives), other fields and events
+/// without data are ignored.
+#[derive(Default)]
+struct EventDecoder {
+    /// Bytes received that do not yet form a complete line.
+    buf: Vec<u8>,
+    /// The data of the event currently being received.
+    data: Vec<u8>,
+    /// Whether the last line ended in `\r`, which may be followed by a `\n`.
+    after_cr: bool,
+}
+
+impl EventDecoder {
+    /// Append bytes received from the stream.
+    fn push(&mut self, chunk: &[u8]) {
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// The data of the next complete event, if any.
+    fn next_event(&mut self) -> Option<Vec<u8>> {
+        while let Some(line) = self.next_line() {
+            if line.is_empty() {
+                let mut data = std::mem::take(&mut self.data);
+                // Multiple data fields are joined by newlines, without a trailing one.
+                data.pop();
+                if !data.is_empty() {
+                    return Some(data);
+                }
+                continue;
+            }
+            if let Some(value) = line.strip_prefix(b"data") {
+                let value = match value {
+                    [] => value,
+                    [b':', value @ ..] => value.strip_prefix(b" ").unwrap_or(value),
+                    // Some other field that starts with `data`.
+                    _ => continue,
+                };
+                self.data.extend_from_slice(value);
+                self.data.push(b'\n');
+            }
+        }
+        None
+    }
+
+    /// The next complete line, without its line ending.
+    fn next_line(&mut self) -> Option<Vec<u8>> {
+        // Skip the `\n` of a `\r\n` whose `\r` ended the previous line.
+        if self.after_cr && !self.buf.is_empty() {
+            self.after_cr = false;
+            if self.buf[0] == b'\n' {
+                self.buf.remove(0);
+            }
+        }
+        let end = self.buf.iter().position(|&b| b == b'\r' || b == b'\n')?;
+        self.after_cr = self.buf[end] == b'\r';
+        let mut line: Vec<u8> = self.buf.drain(..=end).collect();
+        line.truncate(end);
+        Some(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EventDecoder;
+
+    fn decode(chunks: &[&[u8]]) -> Vec<Vec<u8>> {
+        let mut decoder = EventDecoder::default();
+        let mut events = vec![];
+        for chunk in chunks {
+            decoder.push(chunk);
+            events.extend(std::iter::from_fn(|| decoder.next_event()));
+        }
+        events
+    }
+
+    #[test]
+    fn line_endings() {
+        let expected = vec![b"1".to_vec(), b"2".to_vec()];
+        assert_eq!(decode(&[b"data: 1\n\ndata: 2\n\n"]), expected);
+        assert_eq!(decode(&[b"data: 1\r\n\r\ndata: 2\r\n\r\n"]), expected);
+        assert_eq!(decode(&[b"data: 1\r\rdata: 2\r\r"]), expected);
+        assert_eq!(decode(&[b"data: 1\r\n\ndata: 2\r\r\n"]), expected);
+    }
+
+    #[test]
+    fn split_across_chunks() {
+        let expected = vec![b"12".to_vec()];
+        assert_eq!(decode(&[b"data: 1", b"2\r", b"\n\r\n"]), expected);
+        assert_eq!(decode(&[b"data:12\r", b"\r"]), expected);
+        assert!(decode(&[b"data: 12\r\n"]).is_empty());
+    }
+
+    #[test]
+    fn fields_and_comments() {
+        let events =
+            decode(&[b": keep-alive\n\nevent: block\ndata: [1,\ndata\ndata: 2]\nid: 3\n\n"]);
+        assert_eq!(events, vec![b"[1,\n\n2]".to_vec()]);
+        assert!(decode(&[b"datum: 1\n\n"]).is_empty());
+        assert!(decode(&[b"data\n\n"]).is_empty());
+    }
+}
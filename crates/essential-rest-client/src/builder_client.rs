@@ -1,14 +1,62 @@
-use crate::handle_response;
+use crate::{
+    config::ClientConfig,
+    decode_json,
+    error::ClientError,
+    node_client::{EssentialNodeClient, BLOCK_PAGE_SIZE},
+    send,
+};
 use clap::builder::styling::Style;
 use essential_builder_types::SolutionSetFailure;
 use essential_node_types::{register_contract_solution, register_program_solution};
 use essential_types::{
-    contract::Contract, solution::SolutionSet, ContentAddress, PredicateAddress, Program,
+    contract::Contract, solution::SolutionSet, ContentAddress, PredicateAddress, Program, Word,
+};
+use reqwest::Client;
+use serde::Serialize;
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
 };
-use reqwest::{Client, ClientBuilder};
 
 const BOLD: Style = Style::new().bold();
 
+/// The number of blocks before the current head that [`EssentialBuilderClient::await_outcome`]
+/// starts searching from, in case the solution set was included before waiting began.
+const AWAIT_LOOKBACK_BLOCKS: Word = 8;
+
+/// The outcome of waiting on a submitted solution set.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SolutionSetOutcome {
+    /// The solution set was included in a block.
+    Included {
+        /// The number of the block that includes the solution set.
+        block_number: Word,
+        /// The index of the solution set within the block.
+        index: u32,
+    },
+    /// The builder attempted to apply the solution set and failed.
+    Failed(SolutionSetFailure<'static>),
+    /// Neither an inclusion nor a failure was observed before the timeout elapsed.
+    TimedOut,
+}
+
+/// Progress events reported by the [`EssentialBuilderClient`] while submitting.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Progress {
+    /// The solution set with the given content address is about to be submitted.
+    Submitting(ContentAddress),
+    /// The solution set with the given content address was accepted by the builder.
+    Submitted(ContentAddress),
+    /// The contract with the given content address was submitted for registration.
+    RegisteredContract(ContentAddress),
+    /// The program with the given content address was submitted for registration.
+    RegisteredProgram(ContentAddress),
+}
+
+/// A hook called with each [`Progress`] event, see [`EssentialBuilderClient::with_progress`].
+pub type ProgressHook = Arc<dyn Fn(&Progress) + Send + Sync>;
+
 /// Client that binds to an Essential builder address.
 #[derive(Clone)]
 pub struct EssentialBuilderClient {
@@ -16,14 +64,77 @@ pub struct EssentialBuilderClient {
     client: Client,
     /// The url to make requests to.
     url: reqwest::Url,
+    /// Timeout and retry configuration applied to each request.
+    config: ClientConfig,
+    /// Called with progress events, if set.
+    progress: Option<ProgressHook>,
+}
+
+/// Information about a builder, as returned by [`EssentialBuilderClient::info`].
+///
+/// The builder API only accepts solution sets and reports their failures. It exposes
+/// neither its version nor the blocks it has built or the registries it validates
+/// against, so block and big bang information comes from the node, see
+/// [`EssentialNodeClient::info`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct BuilderInfo {
+    /// The round trip time of the builder's health check.
+    pub latency: Duration,
 }
 
 impl EssentialBuilderClient {
-    /// Create a new client with the given address.
-    pub fn new(addr: String) -> anyhow::Result<Self> {
-        let client = ClientBuilder::new().http2_prior_knowledge().build()?;
+    /// Create a new client with the given address and the default [`ClientConfig`].
+    pub fn new(addr: String) -> Result<Self, ClientError> {
+        Self::with_config(addr, ClientConfig::default())
+    }
+
+    /// Create a new client with the given address and configuration.
+    pub fn with_config(addr: String, config: ClientConfig) -> Result<Self, ClientError> {
+        let client = config.build_client()?;
         let url = reqwest::Url::parse(&addr)?;
-        Ok(Self { client, url })
+        Ok(Self {
+            client,
+            url,
+            config,
+            progress: None,
+        })
+    }
+
+    /// Report progress to the given hook.
+    ///
+    /// The client itself never prints. Use [`print_progress`] as the hook for the
+    /// standard CLI output.
+    pub fn with_progress(mut self, hook: impl Fn(&Progress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(hook));
+        self
+    }
+
+    /// Pass the given event to the progress hook, if any.
+    fn report(&self, progress: Progress) {
+        if let Some(hook) = &self.progress {
+            hook(&progress);
+        }
+    }
+
+    /// The configuration used by this client.
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// Check that the builder is reachable and healthy.
+    pub async fn health_check(&self) -> Result<(), ClientError> {
+        let url = self.url.join("/")?;
+        send(&self.config, self.client.get(url)).await?;
+        Ok(())
+    }
+
+    /// Get information about the builder, checking that it is reachable along the way.
+    pub async fn info(&self) -> Result<BuilderInfo, ClientError> {
+        let start = Instant::now();
+        self.health_check().await?;
+        Ok(BuilderInfo {
+            latency: start.elapsed(),
+        })
     }
 
     /// Register contract.
@@ -35,21 +146,16 @@ impl EssentialBuilderClient {
         program_registry: &PredicateAddress,
         contract: &Contract,
         programs: &[Program],
-    ) -> anyhow::Result<ContentAddress> {
+    ) -> Result<ContentAddress, ClientError> {
         let contract_ca = essential_hash::content_addr(contract);
-        let mut solutions = vec![];
-        solutions.push(register_contract_solution(
-            contract_registry.clone(),
+        let solution_set = register_contract_solution_set(
+            contract_registry,
+            program_registry,
             contract,
-        )?);
-        solutions.extend(
-            programs
-                .iter()
-                .map(|p| register_program_solution(program_registry.clone(), p)),
-        );
-        let solution_set = SolutionSet { solutions };
+            programs,
+        )?;
         let output = self.submit_solution_set(&solution_set).await?;
-        print_registered_contract(&contract_ca);
+        self.report(Progress::RegisteredContract(contract_ca));
         Ok(output)
     }
 
@@ -72,17 +178,19 @@ impl EssentialBuilderClient {
     pub async fn submit_solution_set(
         &self,
         solution_set: &SolutionSet,
-    ) -> anyhow::Result<ContentAddress> {
+    ) -> Result<ContentAddress, ClientError> {
         let solution_ca = essential_hash::content_addr(solution_set);
-        print_submitting(&solution_ca);
+        self.report(Progress::Submitting(solution_ca.clone()));
         let url = self.url.join("/submit-solution-set")?;
-        let response =
-            handle_response(self.client.post(url).json(solution_set).send().await?).await?;
-        let output = response.json::<ContentAddress>().await?;
+        let response = send(&self.config, self.client.post(url).json(solution_set)).await?;
+        let output: ContentAddress = decode_json(response).await?;
         if solution_ca != output {
-            anyhow::bail!("The content address of the submitted solution set differs from expected. May be a serialization error.");
+            return Err(ClientError::ContentAddressMismatch {
+                expected: solution_ca,
+                received: output,
+            });
         }
-        print_submitted();
+        self.report(Progress::Submitted(output.clone()));
         Ok(output)
     }
 
@@ -93,14 +201,14 @@ impl EssentialBuilderClient {
         &self,
         program_registry: &PredicateAddress,
         program: &Program,
-    ) -> anyhow::Result<ContentAddress> {
+    ) -> Result<ContentAddress, ClientError> {
         let program_ca = essential_hash::content_addr(program);
         let program_solution = register_program_solution(program_registry.clone(), program);
         let solution_set = SolutionSet {
             solutions: vec![program_solution],
         };
         let output = self.submit_solution_set(&solution_set).await?;
-        print_registered_program(&program_ca);
+        self.report(Progress::RegisteredProgram(program_ca));
         Ok(output)
     }
 
@@ -124,56 +232,142 @@ impl EssentialBuilderClient {
     /// real ownership over a solution. Remember if two of the same solution are submitted
     /// at the same time then it is as if only one was submitted.
     ///
-    /// If you are interested in "has my solution worked" then it probably makes more
-    /// sense to query the state of the contract that you were trying to change.
+    /// If you are interested in "has my solution worked" then see
+    /// [`EssentialBuilderClient::await_outcome`], or query the state of the contract
+    /// that you were trying to change.
     pub async fn latest_solution_failures(
         &self,
         solution_ca: &ContentAddress,
         limit: u32,
-    ) -> anyhow::Result<Vec<SolutionSetFailure<'static>>> {
-        let url = self
-            .url
-            .join(&format!("/latest_solution_failures/{solution_ca}/{limit}"))?;
-        let response = handle_response(self.client.get(url).send().await?).await?;
-        Ok(response.json::<Vec<SolutionSetFailure<'static>>>().await?)
+    ) -> Result<Vec<SolutionSetFailure<'static>>, ClientError> {
+        let url = self.url.join(&format!(
+            "/latest-solution-set-failures/{solution_ca}/{limit}"
+        ))?;
+        let response = send(&self.config, self.client.get(url)).await?;
+        decode_json(response).await
     }
-}
 
-/// Print the "Submitting ..." output.
-fn print_submitting(ca: &ContentAddress) {
-    println!(
-        "  {}Submitting{} solution set {}",
-        BOLD.render(),
-        BOLD.render_reset(),
-        ca,
-    );
+    /// Wait for the outcome of the solution set with the given content address.
+    ///
+    /// Watches the node's blocks for the solution set and polls the builder for failures
+    /// until either is observed or the `timeout` elapses. Only failures recorded from a
+    /// few blocks before the current head onwards are considered, so that failures of an
+    /// earlier submission of the same solution set are not reported.
+    ///
+    /// Polls at the [`ClientConfig::poll_interval`] of this client.
+    pub async fn await_outcome(
+        &self,
+        node: &EssentialNodeClient,
+        solution_set_ca: &ContentAddress,
+        timeout: Duration,
+    ) -> Result<SolutionSetOutcome, ClientError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let start = node
+            .latest_block_number()
+            .await?
+            .map_or(0, |head| head.saturating_sub(AWAIT_LOOKBACK_BLOCKS));
+        let mut next = start;
+        loop {
+            let blocks = node
+                .list_blocks(next..next.saturating_add(BLOCK_PAGE_SIZE))
+                .await?;
+            for block in &blocks {
+                let position = block
+                    .solution_sets
+                    .iter()
+                    .position(|set| essential_hash::content_addr(set) == *solution_set_ca);
+                if let Some(ix) = position {
+                    return Ok(SolutionSetOutcome::Included {
+                        block_number: block.header.number,
+                        index: ix as u32,
+                    });
+                }
+            }
+            let caught_up = (blocks.len() as Word) < BLOCK_PAGE_SIZE;
+            if let Some(block) = blocks.last() {
+                next = block.header.number.saturating_add(1);
+            }
+
+            let failures = self.latest_solution_failures(solution_set_ca, 1).await?;
+            if let Some(failure) = failures.into_iter().find(|f| f.attempt_block_num >= start) {
+                return Ok(SolutionSetOutcome::Failed(failure));
+            }
+
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                return Ok(SolutionSetOutcome::TimedOut);
+            }
+            if caught_up {
+                tokio::time::sleep(self.config.poll_interval().min(deadline - now)).await;
+            }
+        }
+    }
 }
 
-/// Print the "Submitted" output.
-fn print_submitted() {
-    println!(
-        "   {}Submitted{} successfully",
-        BOLD.render(),
-        BOLD.render_reset(),
-    );
+impl fmt::Display for SolutionSetOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Included {
+                block_number,
+                index,
+            } => write!(f, "included in block {block_number} at index {index}"),
+            Self::Failed(failure) => write!(
+                f,
+                "failed in block {} at index {}: {}",
+                failure.attempt_block_num, failure.attempt_solution_set_ix, failure.err_msg
+            ),
+            Self::TimedOut => write!(f, "timed out waiting for an outcome"),
+        }
+    }
 }
 
-/// Print the "Submitted contract ... for registration ..." output.
-fn print_registered_contract(ca: &ContentAddress) {
-    println!(
-        "   {}Submitted{} contract {} for registration successfully",
-        BOLD.render(),
-        BOLD.render_reset(),
-        ca,
+/// Create the solution set that registers the given contract and its programs.
+///
+/// This is the solution set submitted by [`EssentialBuilderClient::register_contract`].
+pub fn register_contract_solution_set(
+    contract_registry: &PredicateAddress,
+    program_registry: &PredicateAddress,
+    contract: &Contract,
+    programs: &[Program],
+) -> Result<SolutionSet, ClientError> {
+    let mut solutions = vec![];
+    solutions.push(register_contract_solution(
+        contract_registry.clone(),
+        contract,
+    )?);
+    solutions.extend(
+        programs
+            .iter()
+            .map(|p| register_program_solution(program_registry.clone(), p)),
     );
+    Ok(SolutionSet { solutions })
 }
 
-/// Print the "Submitted program ... for registration ..." output.
-fn print_registered_program(ca: &ContentAddress) {
-    println!(
-        "   {}Submitted{} program {} for registration successfully",
-        BOLD.render(),
-        BOLD.render_reset(),
-        ca,
-    );
+/// Print the given progress event in the style of the Essential CLI tools.
+pub fn print_progress(progress: &Progress) {
+    match progress {
+        Progress::Submitting(ca) => println!(
+            "  {}Submitting{} solution set {}",
+            BOLD.render(),
+            BOLD.render_reset(),
+            ca,
+        ),
+        Progress::Submitted(_) => println!(
+            "   {}Submitted{} successfully",
+            BOLD.render(),
+            BOLD.render_reset(),
+        ),
+        Progress::RegisteredContract(ca) => println!(
+            "   {}Submitted{} contract {} for registration successfully",
+            BOLD.render(),
+            BOLD.render_reset(),
+            ca,
+        ),
+        Progress::RegisteredProgram(ca) => println!(
+            "   {}Submitted{} program {} for registration successfully",
+            BOLD.render(),
+            BOLD.render_reset(),
+            ca,
+        ),
+    }
 }
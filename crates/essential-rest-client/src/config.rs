@@ -0,0 +1,268 @@
+use crate::error::ClientError;
+use reqwest::{Client, ClientBuilder, Method, StatusCode};
+use std::time::Duration;
+
+/// Timeout, retry and backoff configuration shared by the node and builder clients.
+///
+/// Construct with [`ClientConfig::builder`], or use the [`Default`] configuration.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClientConfig {
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    retry_statuses: Vec<StatusCode>,
+    retry_submissions: bool,
+    max_concurrent_requests: usize,
+    poll_interval: Duration,
+}
+
+/// Builds a [`ClientConfig`], starting from the default configuration.
+#[derive(Clone, Debug, Default)]
+pub struct ClientConfigBuilder {
+    config: ClientConfig,
+}
+
+impl ClientConfig {
+    /// The default timeout applied to each request attempt.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+    /// The default timeout applied to establishing a connection.
+    pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+    /// The default maximum number of retries.
+    pub const DEFAULT_MAX_RETRIES: u32 = 3;
+    /// The default delay before the first retry.
+    pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+    /// The default upper bound on the delay between retries.
+    pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(5);
+    /// The default set of status codes that are retried.
+    pub const DEFAULT_RETRY_STATUSES: [StatusCode; 4] = [
+        StatusCode::TOO_MANY_REQUESTS,
+        StatusCode::BAD_GATEWAY,
+        StatusCode::SERVICE_UNAVAILABLE,
+        StatusCode::GATEWAY_TIMEOUT,
+    ];
+    /// The default maximum number of concurrent requests made by batch queries.
+    pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 16;
+    /// The default interval between polls while waiting on a solution set outcome.
+    pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Start building a configuration from the defaults.
+    pub fn builder() -> ClientConfigBuilder {
+        ClientConfigBuilder::default()
+    }
+
+    /// The timeout applied to each individual request attempt, if any.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// The timeout applied to establishing a connection, if any.
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout
+    }
+
+    /// The maximum number of times a failed request is retried.
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// The delay before the first retry.
+    pub fn initial_backoff(&self) -> Duration {
+        self.initial_backoff
+    }
+
+    /// The upper bound on the delay between retries.
+    pub fn max_backoff(&self) -> Duration {
+        self.max_backoff
+    }
+
+    /// Response status codes that are considered transient and are retried.
+    pub fn retry_statuses(&self) -> &[StatusCode] {
+        &self.retry_statuses
+    }
+
+    /// Whether or not failed solution set submissions are retried.
+    pub fn retry_submissions(&self) -> bool {
+        self.retry_submissions
+    }
+
+    /// The maximum number of requests made concurrently by batch queries.
+    pub fn max_concurrent_requests(&self) -> usize {
+        self.max_concurrent_requests
+    }
+
+    /// How often to poll the node and builder while waiting on a solution set outcome.
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    /// Build the underlying `reqwest` client.
+    pub(crate) fn build_client(&self) -> Result<Client, ClientError> {
+        let mut builder = ClientBuilder::new().http2_prior_knowledge();
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        Ok(builder.build()?)
+    }
+
+    /// The delay before the retry following the given number of failed attempts.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+
+    /// Whether or not requests with the given method may be retried at all.
+    ///
+    /// Only idempotent requests are retried, unless retrying submissions is enabled.
+    pub(crate) fn may_retry(&self, method: &Method) -> bool {
+        method.is_idempotent() || self.retry_submissions
+    }
+
+    /// Whether or not the request that produced the given error should be retried.
+    pub(crate) fn is_retryable(&self, err: &ClientError) -> bool {
+        match err {
+            ClientError::Transport(err) => err.is_connect() || err.is_timeout(),
+            ClientError::Http { status, .. } => self.retry_statuses.contains(status),
+            _ => false,
+        }
+    }
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Self::DEFAULT_TIMEOUT),
+            connect_timeout: Some(Self::DEFAULT_CONNECT_TIMEOUT),
+            max_retries: Self::DEFAULT_MAX_RETRIES,
+            initial_backoff: Self::DEFAULT_INITIAL_BACKOFF,
+            max_backoff: Self::DEFAULT_MAX_BACKOFF,
+            retry_statuses: Self::DEFAULT_RETRY_STATUSES.to_vec(),
+            retry_submissions: false,
+            max_concurrent_requests: Self::DEFAULT_MAX_CONCURRENT_REQUESTS,
+            poll_interval: Self::DEFAULT_POLL_INTERVAL,
+        }
+    }
+}
+
+impl ClientConfigBuilder {
+    /// The timeout applied to each individual request attempt.
+    ///
+    /// `None` disables the timeout.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    /// The timeout applied to establishing a connection.
+    ///
+    /// `None` disables the timeout.
+    pub fn connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.connect_timeout = timeout;
+        self
+    }
+
+    /// The maximum number of times a failed request is retried.
+    ///
+    /// `0` disables retries.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.config.max_retries = max_retries;
+        self
+    }
+
+    /// The delay before the first retry.
+    ///
+    /// The delay doubles with each subsequent retry up to the max backoff.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.config.initial_backoff = backoff;
+        self
+    }
+
+    /// The upper bound on the delay between retries.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.config.max_backoff = backoff;
+        self
+    }
+
+    /// Response status codes that are considered transient and are retried.
+    ///
+    /// Connection failures and timeouts are always considered transient.
+    pub fn retry_statuses(mut self, statuses: impl IntoIterator<Item = StatusCode>) -> Self {
+        self.config.retry_statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Whether or not failed solution set submissions are retried.
+    ///
+    /// Submissions are not idempotent requests, so they are only retried when enabled.
+    /// The builder deduplicates identical solution sets, so a retried submission that
+    /// already reached the builder is not included twice.
+    pub fn retry_submissions(mut self, retry: bool) -> Self {
+        self.config.retry_submissions = retry;
+        self
+    }
+
+    /// The maximum number of requests made concurrently by batch queries.
+    ///
+    /// Values below `1` are treated as `1`.
+    pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+        self.config.max_concurrent_requests = max.max(1);
+        self
+    }
+
+    /// How often to poll the node and builder while waiting on a solution set outcome.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.config.poll_interval = interval;
+        self
+    }
+
+    /// Finish building the configuration.
+    pub fn build(self) -> ClientConfig {
+        self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let config = ClientConfig::builder()
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(500))
+            .build();
+        let delays: Vec<_> = (0..5).map(|attempt| config.backoff(attempt)).collect();
+        assert_eq!(
+            delays,
+            [100, 200, 400, 500, 500]
+                .map(Duration::from_millis)
+                .to_vec()
+        );
+        assert_eq!(config.backoff(u32::MAX), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn only_idempotent_requests_are_retried_by_default() {
+        let config = ClientConfig::default();
+        assert!(config.may_retry(&Method::GET));
+        assert!(!config.may_retry(&Method::POST));
+        let config = ClientConfig::builder().retry_submissions(true).build();
+        assert!(config.may_retry(&Method::POST));
+    }
+
+    #[test]
+    fn retry_statuses_are_configurable() {
+        let config = ClientConfig::builder()
+            .retry_statuses([StatusCode::INTERNAL_SERVER_ERROR])
+            .build();
+        let err = |status| ClientError::Http {
+            status,
+            body: String::new(),
+        };
+        assert!(config.is_retryable(&err(StatusCode::INTERNAL_SERVER_ERROR)));
+        assert!(!config.is_retryable(&err(StatusCode::SERVICE_UNAVAILABLE)));
+        assert!(!ClientConfig::default().is_retryable(&err(StatusCode::BAD_REQUEST)));
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::Context;
+use essential_types::{ContentAddress, PredicateAddress};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+/// The record of contracts deployed to each network, as written to `deployments.toml`.
+///
+/// ```toml
+/// [networks.testnet]
+/// builder_address = "https://..."
+/// node_address = "https://..."
+///
+/// [networks.testnet.contracts.counter]
+/// address = "..."
+/// programs = ["..."]
+/// solution_set = "..."
+/// timestamp = 1730000000
+///
+/// [networks.testnet.contracts.counter.predicates]
+/// "::Increment" = "..."
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Deployments {
+    /// The deployments to each network, by network name.
+    #[serde(default)]
+    pub networks: BTreeMap<String, Network>,
+}
+
+/// The deployments to a single network.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Network {
+    /// The builder the contracts were submitted to.
+    pub builder_address: String,
+    /// The node used while deploying, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_address: Option<String>,
+    /// The deployed contracts, by package name.
+    #[serde(default)]
+    pub contracts: BTreeMap<String, Deployment>,
+}
+
+/// A single deployed contract.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Deployment {
+    /// The content address of the contract.
+    pub address: ContentAddress,
+    /// The content address of each of the contract's predicates, by predicate name.
+    #[serde(default)]
+    pub predicates: BTreeMap<String, ContentAddress>,
+    /// The content addresses of the contract's programs.
+    #[serde(default)]
+    pub programs: Vec<ContentAddress>,
+    /// The solution set that registered the contract.
+    ///
+    /// `None` if the contract was found to be registered already.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solution_set: Option<ContentAddress>,
+    /// When the deployment was recorded, in seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+impl Deployments {
+    /// The name of the file deployments are recorded in.
+    pub const FILE_NAME: &'static str = "deployments.toml";
+
+    /// Read the deployments recorded at the given path.
+    ///
+    /// Returns no deployments if the file does not exist.
+    pub async fn from_path(path: &Path) -> anyhow::Result<Self> {
+        if !tokio::fs::try_exists(path).await? {
+            return Ok(Self::default());
+        }
+        let string = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("failed to read deployments from file {path:?}"))?;
+        let deployments = toml::from_str(&string)
+            .with_context(|| format!("failed to parse deployments from TOML at {path:?}"))?;
+        Ok(deployments)
+    }
+
+    /// Write the deployments to the given path, replacing any existing file.
+    pub async fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let string = toml::to_string_pretty(self)?;
+        tokio::fs::write(path, string)
+            .await
+            .with_context(|| format!("failed to write deployments to file {path:?}"))
+    }
+
+    /// The deployment of the named contract to the named network.
+    pub fn contract(&self, network: &str, name: &str) -> anyhow::Result<&Deployment> {
+        self.networks
+            .get(network)
+            .ok_or_else(|| anyhow::anyhow!("No deployments to network `{network}`"))?
+            .contracts
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("No deployment of `{name}` to network `{network}`"))
+    }
+}
+
+impl Deployment {
+    /// The address of the named predicate of this contract.
+    pub fn predicate_address(&self, name: &str) -> Option<PredicateAddress> {
+        let predicate = self.predicates.get(name)?;
+        Some(PredicateAddress {
+            contract: self.address.clone(),
+            predicate: predicate.clone(),
+        })
+    }
+}
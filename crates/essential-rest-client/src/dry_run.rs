@@ -0,0 +1,139 @@
+use crate::node_client::{EssentialNodeClient, BLOCK_PAGE_SIZE};
+use essential_node::{
+    db::{
+        pool::{Config, Source},
+        ConnectionPool,
+    },
+    validate::{ValidateFailure, ValidateOutcome},
+};
+use essential_node_types::BigBang;
+use essential_types::{solution::SolutionSet, Word};
+use serde::Serialize;
+use std::{fmt, sync::Arc};
+
+/// A local, in-memory copy of a node's blocks.
+///
+/// The node has no endpoint for validating a solution set without submitting it, so the
+/// node's blocks are copied into a local database and solution sets are validated against
+/// that instead.
+///
+/// The snapshot holds every block from the big bang onwards in memory, solutions included,
+/// and the first sync fetches all of them from the node. Both grow with the length of the
+/// chain, so dry runs suit development chains rather than nodes with a long history. Later
+/// syncs only fetch new blocks, so reuse a snapshot to validate many solution sets.
+pub struct NodeSnapshot {
+    conn: ConnectionPool,
+    /// The number of the next block to sync.
+    next: Word,
+}
+
+/// The result of validating a solution set against a [`NodeSnapshot`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub enum DryRunOutcome {
+    /// The solution set is valid and would be accepted as of the latest synced block.
+    Valid {
+        /// The total gas consumed by the solution set.
+        total_gas: u64,
+    },
+    /// The solution set would fail.
+    Invalid {
+        /// Which solution, predicate or program would fail and why.
+        reason: String,
+    },
+}
+
+impl NodeSnapshot {
+    /// Create an empty snapshot.
+    pub fn new() -> anyhow::Result<Self> {
+        let conf = Config {
+            source: Source::Memory(uuid::Uuid::new_v4().into()),
+            ..Default::default()
+        };
+        let conn = ConnectionPool::with_tables(&conf)?;
+        Ok(Self { conn, next: 0 })
+    }
+
+    /// Copy any blocks the node has finalized since the last sync into the snapshot.
+    pub async fn sync(&mut self, node: &EssentialNodeClient) -> anyhow::Result<()> {
+        loop {
+            let blocks = node
+                .list_blocks(self.next..self.next.saturating_add(BLOCK_PAGE_SIZE))
+                .await?;
+            let full_page = blocks.len() as Word == BLOCK_PAGE_SIZE;
+            for block in blocks {
+                let number = block.header.number;
+                let block_ca = self.conn.insert_block(Arc::new(block)).await?;
+                self.conn.finalize_block(block_ca).await?;
+                self.next = number.saturating_add(1);
+            }
+            if !full_page {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Validate the given solution set against the state of the snapshot, without
+    /// submitting it anywhere.
+    pub async fn dry_run(
+        &self,
+        big_bang: &BigBang,
+        solution_set: SolutionSet,
+    ) -> anyhow::Result<DryRunOutcome> {
+        let outcome = essential_node::validate_solution_set_dry_run(
+            &self.conn,
+            &big_bang.contract_registry.contract,
+            &big_bang.program_registry.contract,
+            solution_set,
+        )
+        .await?;
+        Ok(match outcome {
+            ValidateOutcome::Valid(valid) => DryRunOutcome::Valid {
+                total_gas: valid.total_gas,
+            },
+            ValidateOutcome::Invalid(invalid) => DryRunOutcome::Invalid {
+                reason: failure_reason(&invalid.failure),
+            },
+        })
+    }
+}
+
+/// Sync a new snapshot of the given node and validate the solution set against it.
+///
+/// This copies every block of the node into memory, see [`NodeSnapshot`]. Prefer a
+/// [`NodeSnapshot`] directly when validating many solution sets against one node.
+pub async fn dry_run(
+    node: &EssentialNodeClient,
+    big_bang: &BigBang,
+    solution_set: SolutionSet,
+) -> anyhow::Result<DryRunOutcome> {
+    let mut snapshot = NodeSnapshot::new()?;
+    snapshot.sync(node).await?;
+    snapshot.dry_run(big_bang, solution_set).await
+}
+
+/// A human readable description of why validation failed.
+fn failure_reason(failure: &ValidateFailure) -> String {
+    match failure {
+        ValidateFailure::MissingPredicate(addr) => format!(
+            "predicate {} of contract {} is not registered",
+            addr.predicate, addr.contract
+        ),
+        ValidateFailure::InvalidPredicate(addr) => format!(
+            "predicate {} of contract {} failed to decode",
+            addr.predicate, addr.contract
+        ),
+        ValidateFailure::MissingProgram(ca) => format!("program {ca} is not registered"),
+        ValidateFailure::InvalidProgram(ca) => format!("program {ca} has an invalid format"),
+        ValidateFailure::PredicatesError(err) => err.to_string(),
+        ValidateFailure::GasOverflow => "total gas exceeds the maximum gas limit".to_string(),
+    }
+}
+
+impl fmt::Display for DryRunOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Valid { total_gas } => write!(f, "would succeed using {total_gas} gas"),
+            Self::Invalid { reason } => write!(f, "would fail: {reason}"),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use essential_types::{predicate::PredicateEncodeError, ContentAddress};
+use reqwest::StatusCode;
+use thiserror::Error;
+
+/// Any error that might occur while making a request to the Essential node or builder.
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The given address could not be parsed as a URL, or an endpoint could not be joined to it.
+    #[error("invalid URL: {0}")]
+    Url(#[from] url::ParseError),
+    /// The request could not be sent or the response could not be received.
+    #[error("request failed: {0}")]
+    Transport(#[from] reqwest::Error),
+    /// The server responded with a non-success status code.
+    #[error("{status}: {body}")]
+    Http {
+        /// The status code returned by the server.
+        status: StatusCode,
+        /// The body of the response, typically describing the error.
+        body: String,
+    },
+    /// The response body could not be decoded into the expected type.
+    #[error("failed to decode response: {0}")]
+    Decode(#[source] serde_json::Error),
+    /// The content address returned by the builder differs from the one computed locally.
+    ///
+    /// This usually indicates a serialization error.
+    #[error("the content address of the submitted solution set differs from expected (expected {expected}, received {received}). May be a serialization error.")]
+    ContentAddressMismatch {
+        /// The content address computed by the client.
+        expected: ContentAddress,
+        /// The content address returned by the builder.
+        received: ContentAddress,
+    },
+    /// A predicate could not be encoded while constructing a registration solution.
+    #[error("failed to encode predicate: {0}")]
+    Encode(#[from] PredicateEncodeError),
+    /// A request with a streaming body cannot be retried, as the body cannot be replayed.
+    #[error("request with a streaming body cannot be retried")]
+    StreamingBody,
+    /// A contract or program read from a registry's state is malformed.
+    #[error("invalid registry entry: {0}")]
+    Registry(String),
+}
+
+impl ClientError {
+    /// The HTTP status code returned by the server, if the error was caused by a
+    /// non-success response.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Http { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
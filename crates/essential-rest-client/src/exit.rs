@@ -0,0 +1,136 @@
+use crate::error::ClientError;
+use clap::builder::styling::Style;
+use std::process::ExitCode;
+
+/// Any failure not covered by a more specific code.
+pub const GENERAL: u8 = 1;
+/// The node or builder could not be reached, or the connection failed.
+pub const NETWORK: u8 = 3;
+/// The node or builder responded with a non-success status.
+pub const REJECTED: u8 = 4;
+/// The submitted contract or solution set was found to be invalid.
+pub const VALIDATION: u8 = 5;
+/// No outcome for a submitted solution set was seen before the timeout elapsed.
+pub const TIMEOUT: u8 = 6;
+/// An input, file or response could not be parsed or decoded.
+///
+/// This is not `2`, which `clap` exits with when the command line arguments are invalid.
+pub const PARSE: u8 = 7;
+
+/// An error indicating a contract or solution set failed validation.
+///
+/// Wrap a validation failure in this type so that [`code`] maps it to [`VALIDATION`].
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct ValidationError(pub String);
+
+/// An error indicating a submitted solution set had no outcome within the timeout.
+///
+/// Wrap a timeout in this type so that [`code`] maps it to [`TIMEOUT`].
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct TimeoutError(pub String);
+
+/// The exit code for the given error.
+///
+/// The first error in the chain with a known class determines the code.
+pub fn code(err: &anyhow::Error) -> u8 {
+    err.chain()
+        .find_map(|cause| {
+            if let Some(err) = cause.downcast_ref::<ClientError>() {
+                return Some(client_error_code(err));
+            }
+            if cause.is::<ValidationError>() {
+                return Some(VALIDATION);
+            }
+            if cause.is::<TimeoutError>() {
+                return Some(TIMEOUT);
+            }
+            if cause.is::<serde_json::Error>()
+                || cause.is::<serde_yaml::Error>()
+                || cause.is::<toml::de::Error>()
+                || cause.is::<hex::FromHexError>()
+                || cause.is::<url::ParseError>()
+            {
+                return Some(PARSE);
+            }
+            None
+        })
+        .unwrap_or(GENERAL)
+}
+
+/// The exit code for the given client error.
+fn client_error_code(err: &ClientError) -> u8 {
+    match err {
+        ClientError::Url(_) | ClientError::Decode(_) => PARSE,
+        ClientError::Transport(err) if err.is_decode() => PARSE,
+        ClientError::Transport(_) => NETWORK,
+        ClientError::Http { .. } => REJECTED,
+        ClientError::Encode(_) => VALIDATION,
+        ClientError::Registry(_) => PARSE,
+        ClientError::ContentAddressMismatch { .. } | ClientError::StreamingBody => GENERAL,
+    }
+}
+
+/// Print the given error to stderr, returning the exit code for the process to exit with.
+///
+/// Binaries return this from `main` so that the process exits with the error's [`code`].
+pub fn report(err: &anyhow::Error) -> ExitCode {
+    let bold = Style::new().bold();
+    eprintln!("{}Error:{} {err:?}", bold.render(), bold.render_reset());
+    ExitCode::from(code(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use reqwest::StatusCode;
+
+    fn client_code(err: ClientError) -> u8 {
+        code(&anyhow::Error::new(err))
+    }
+
+    #[test]
+    fn client_errors() {
+        let http = ClientError::Http {
+            status: StatusCode::BAD_REQUEST,
+            body: String::new(),
+        };
+        assert_eq!(client_code(http), REJECTED);
+        let url = url::Url::parse("not a url").unwrap_err();
+        assert_eq!(client_code(ClientError::Url(url)), PARSE);
+        let json = serde_json::from_str::<u8>("x").unwrap_err();
+        assert_eq!(client_code(ClientError::Decode(json)), PARSE);
+        assert_eq!(client_code(ClientError::Registry(String::new())), PARSE);
+        let mismatch = ClientError::ContentAddressMismatch {
+            expected: essential_types::ContentAddress([0; 32]),
+            received: essential_types::ContentAddress([1; 32]),
+        };
+        assert_eq!(client_code(mismatch), GENERAL);
+    }
+
+    #[tokio::test]
+    async fn transport_error() {
+        // Nothing listens on a port that was bound and then released.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let err = reqwest::get(format!("http://{addr}")).await.unwrap_err();
+        assert_eq!(client_code(ClientError::Transport(err)), NETWORK);
+    }
+
+    #[test]
+    fn first_known_cause_in_chain() {
+        let err =
+            anyhow::Error::new(ValidationError("invalid".to_string())).context("while deploying");
+        assert_eq!(code(&err), VALIDATION);
+        let err = Err::<(), _>(TimeoutError("timed out".to_string()))
+            .context("while waiting")
+            .unwrap_err();
+        assert_eq!(code(&err), TIMEOUT);
+        let err = serde_yaml::from_str::<u8>("[").unwrap_err();
+        assert_eq!(code(&anyhow::Error::new(err).context("big bang")), PARSE);
+        assert_eq!(code(&anyhow::anyhow!("something else")), GENERAL);
+    }
+}
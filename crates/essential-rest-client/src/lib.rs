@@ -3,12 +3,28 @@
 #![deny(missing_docs)]
 
 use anyhow::Context;
+use config::ClientConfig;
+use error::ClientError;
+use serde::de::DeserializeOwned;
 use std::path::Path;
 
+use essential_node_types::BigBang;
 use essential_types::{Contract, Program};
 
+mod block_stream;
 /// Client library for sending requests to the Essential builder.
 pub mod builder_client;
+/// Timeout, retry and backoff configuration for the node and builder clients.
+pub mod config;
+/// The record of deployed contracts written by `pint-deploy`.
+pub mod deployments;
+/// Validation of solution sets against a local snapshot of a node, without submitting them.
+#[cfg(feature = "dry-run")]
+pub mod dry_run;
+/// Errors returned by the node and builder clients.
+pub mod error;
+/// Process exit codes for the CLI tools, by class of error.
+pub mod exit;
 /// Client library for sending requests to the Essential node.
 pub mod node_client;
 
@@ -26,13 +42,95 @@ pub async fn contract_from_path(contract_path: &Path) -> anyhow::Result<(Contrac
     Ok((contract, programs))
 }
 
-/// Map `reqwest::Response` into `anyhow::Result`.
-async fn handle_response(response: reqwest::Response) -> anyhow::Result<reqwest::Response> {
+/// A helper for reading a [`BigBang`] configuration from a YAML file at the given path.
+///
+/// This is the same format accepted by the node and builder.
+pub async fn big_bang_from_path(big_bang_path: &Path) -> anyhow::Result<BigBang> {
+    let big_bang_string = tokio::fs::read_to_string(&big_bang_path)
+        .await
+        .with_context(|| format!("failed to read big bang config from file {big_bang_path:?}"))?;
+    let big_bang = serde_yaml::from_str(&big_bang_string).with_context(|| {
+        format!("failed to parse big bang config from YAML at {big_bang_path:?}")
+    })?;
+    Ok(big_bang)
+}
+
+/// Read the [`BigBang`] configuration at the given path, or use the default configuration
+/// if no path is given.
+pub async fn big_bang_or_default(big_bang_path: Option<&Path>) -> anyhow::Result<BigBang> {
+    match big_bang_path {
+        Some(path) => big_bang_from_path(path).await,
+        None => Ok(BigBang::default()),
+    }
+}
+
+/// Send the given request, retrying transient failures according to the given config.
+///
+/// Non-success responses are mapped into a [`ClientError::Http`].
+async fn send(
+    config: &ClientConfig,
+    request: reqwest::RequestBuilder,
+) -> Result<reqwest::Response, ClientError> {
+    send_with_timeout(config, request, config.timeout()).await
+}
+
+/// Like [`send`], but with an explicit per-attempt timeout in place of the configured one.
+///
+/// The timeout covers reading the entire body, so long-lived streaming requests pass `None`.
+///
+/// Only idempotent requests are retried, see [`ClientConfig::retry_submissions`].
+async fn send_with_timeout(
+    config: &ClientConfig,
+    request: reqwest::RequestBuilder,
+    timeout: Option<std::time::Duration>,
+) -> Result<reqwest::Response, ClientError> {
+    let (client, request) = request.build_split();
+    let mut request = request?;
+    *request.timeout_mut() = timeout;
+    let max_retries = if config.may_retry(request.method()) {
+        config.max_retries()
+    } else {
+        0
+    };
+    let mut attempt = 0;
+    loop {
+        if attempt >= max_retries {
+            return handle_attempt(client.execute(request).await).await;
+        }
+        let req = request.try_clone().ok_or(ClientError::StreamingBody)?;
+        match handle_attempt(client.execute(req).await).await {
+            Err(err) if config.is_retryable(&err) => {
+                tokio::time::sleep(config.backoff(attempt)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Map the result of a single request attempt into the client's result.
+async fn handle_attempt(
+    result: Result<reqwest::Response, reqwest::Error>,
+) -> Result<reqwest::Response, ClientError> {
+    match result {
+        Ok(response) => handle_response(response).await,
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Map a non-success `reqwest::Response` into a [`ClientError::Http`].
+async fn handle_response(response: reqwest::Response) -> Result<reqwest::Response, ClientError> {
     let status = response.status();
     if status.is_success() {
         Ok(response)
     } else {
-        let text = response.text().await?;
-        Err(anyhow::anyhow!("{}: {}", status, text))
+        let body = response.text().await?;
+        Err(ClientError::Http { status, body })
     }
 }
+
+/// Decode the JSON body of the given response.
+async fn decode_json<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, ClientError> {
+    let bytes = response.bytes().await?;
+    serde_json::from_slice(&bytes).map_err(ClientError::Decode)
+}
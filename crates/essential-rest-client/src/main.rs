@@ -1,24 +1,74 @@
-use clap::{Parser, Subcommand};
-use essential_node_types::BigBang;
+use clap::{Parser, Subcommand, ValueEnum};
+use essential_builder_types::SolutionSetFailure;
+use essential_node_types::Block;
 use essential_rest_client::{
-    builder_client::EssentialBuilderClient, contract_from_path, node_client::EssentialNodeClient,
+    big_bang_or_default,
+    builder_client::{print_progress, BuilderInfo, EssentialBuilderClient},
+    contract_from_path, exit,
+    node_client::{EssentialNodeClient, NodeInfo},
 };
 use essential_types::{
-    convert::words_from_hex_str, solution::SolutionSet, ContentAddress, Key, Word,
+    convert::words_from_hex_str, solution::SolutionSet, ContentAddress, Contract, Key, Program,
+    Value, Word,
 };
-use std::{path::PathBuf, str::FromStr};
+use serde::Serialize;
+use std::{fmt, path::PathBuf, process::ExitCode, str::FromStr};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 /// Essential REST Client
 struct Cli {
+    /// How to write the result of the command to stdout.
+    #[arg(long, value_enum, global = true, default_value_t = Format::Json)]
+    format: Format,
     #[command(subcommand)]
     command: Command,
 }
 
+/// The output format of the CLI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// A single JSON document.
+    Json,
+    /// Human readable text, including progress while submitting.
+    Human,
+    /// No output. Success or failure is indicated by the exit status.
+    Quiet,
+}
+
+/// The result of a command.
+///
+/// Serialized as the single JSON document written in `--format json`.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Output {
+    NodeInfo(NodeInfo),
+    BuilderInfo(BuilderInfo),
+    Blocks(Vec<Block>),
+    State(Option<Value>),
+    RegisteredContract {
+        contract: ContentAddress,
+        solution_set: ContentAddress,
+    },
+    SubmittedSolutionSet {
+        solution_set: ContentAddress,
+    },
+    #[cfg(feature = "dry-run")]
+    DryRun(essential_rest_client::dry_run::DryRunOutcome),
+    Failures(Vec<SolutionSetFailure<'static>>),
+    /// In the form read by `contract_from_path`.
+    Contract((Contract, Vec<Program>)),
+    Program(Program),
+}
+
 /// Commands for calling functions.
 #[derive(Subcommand, Debug)]
 enum Command {
+    /// Check that a node or builder is reachable and print information about it.
+    Ping {
+        #[command(subcommand)]
+        target: PingTarget,
+    },
     /// List blocks in the given block number range.
     ListBlocks {
         /// The endpoint of node to bind to.
@@ -36,6 +86,37 @@ enum Command {
         /// Key to query, encoded as hex.
         #[arg(value_parser = words_from_hex_str)]
         key: Key,
+        /// Query the state as of the end of this block number instead of the head.
+        #[arg(short, long)]
+        block: Option<Word>,
+    },
+    /// Fetch a registered contract and its programs from the node's registries.
+    ///
+    /// Printed as the JSON `(Contract, Vec<Program>)` accepted by `register-contract`.
+    GetContract {
+        /// The endpoint of node to bind to.
+        node_address: String,
+        /// Address of the contract to fetch, encoded as hex.
+        #[arg(short, long)]
+        content_address: ContentAddress,
+        /// Path to the big bang configuration of the chain as YAML.
+        ///
+        /// If not specified, the default big bang configuration is used.
+        #[arg(long)]
+        big_bang: Option<PathBuf>,
+    },
+    /// Fetch a registered program from the node's program registry.
+    GetProgram {
+        /// The endpoint of node to bind to.
+        node_address: String,
+        /// Address of the program to fetch, encoded as hex.
+        #[arg(short, long)]
+        content_address: ContentAddress,
+        /// Path to the big bang configuration of the chain as YAML.
+        ///
+        /// If not specified, the default big bang configuration is used.
+        #[arg(long)]
+        big_bang: Option<PathBuf>,
     },
     /// Register a contract.
     RegisterContract {
@@ -43,6 +124,11 @@ enum Command {
         builder_address: String,
         /// Path to the contract file as a json `Contract`.
         contract: PathBuf,
+        /// Path to the big bang configuration of the chain as YAML.
+        ///
+        /// If not specified, the default big bang configuration is used.
+        #[arg(long)]
+        big_bang: Option<PathBuf>,
     },
     /// Submit a solution set.
     SubmitSolutionSet {
@@ -50,6 +136,23 @@ enum Command {
         builder_address: String,
         /// Path to the solution set file as a json `SolutionSet`.
         solution_set: PathBuf,
+        /// Validate the solution set against a local snapshot of the node instead of
+        /// submitting it.
+        ///
+        /// The snapshot copies every block of the node into memory.
+        #[cfg(feature = "dry-run")]
+        #[arg(long, requires = "node_address")]
+        dry_run: bool,
+        /// The endpoint of the node to validate against with `--dry-run`.
+        #[cfg(feature = "dry-run")]
+        #[arg(long, requires = "dry_run")]
+        node_address: Option<String>,
+        /// Path to the big bang configuration of the chain as YAML, used with `--dry-run`.
+        ///
+        /// If not specified, the default big bang configuration is used.
+        #[cfg(feature = "dry-run")]
+        #[arg(long, requires = "dry_run")]
+        big_bang: Option<PathBuf>,
     },
     /// Get the latest failures for solution.
     LatestSolutionFailures {
@@ -63,46 +166,137 @@ enum Command {
     },
 }
 
+/// The service to ping.
+#[derive(Subcommand, Debug)]
+enum PingTarget {
+    /// Ping a node.
+    Node {
+        /// The endpoint of node to bind to.
+        node_address: String,
+    },
+    /// Ping a builder.
+    Builder {
+        /// The endpoint of builder to bind to.
+        builder_address: String,
+    },
+}
+
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let args = Cli::parse();
-    if let Err(err) = run(args).await {
-        eprintln!("Command failed because: {}", err);
+    let format = args.format;
+    let output = match run(args).await {
+        Ok(output) => output,
+        Err(err) => return exit::report(&err),
+    };
+    match format {
+        Format::Json => match serde_json::to_string(&output) {
+            Ok(json) => println!("{json}"),
+            Err(err) => return exit::report(&err.into()),
+        },
+        Format::Human => println!("{output}"),
+        Format::Quiet => (),
     }
+    ExitCode::SUCCESS
 }
 
-async fn run(cli: Cli) -> anyhow::Result<()> {
-    let Cli { command } = cli;
-    match command {
+async fn run(cli: Cli) -> anyhow::Result<Output> {
+    let Cli { format, command } = cli;
+    let output = match command {
+        Command::Ping { target } => match target {
+            PingTarget::Node { node_address } => {
+                let node_client = EssentialNodeClient::new(node_address)?;
+                Output::NodeInfo(node_client.info().await?)
+            }
+            PingTarget::Builder { builder_address } => {
+                let builder_client = builder_client(builder_address, format)?;
+                Output::BuilderInfo(builder_client.info().await?)
+            }
+        },
         Command::ListBlocks {
             node_address,
             range,
         } => {
             let node_client = EssentialNodeClient::new(node_address)?;
-            let output = node_client.list_blocks(range.start..range.end).await?;
-            println!("{}", serde_json::to_string(&output)?);
+            Output::Blocks(node_client.list_blocks(range.start..range.end).await?)
         }
         Command::QueryState {
             node_address,
             content_address,
             key,
+            block,
         } => {
             let node_client = EssentialNodeClient::new(node_address)?;
-            let output = node_client
-                .query_state(content_address.to_owned(), key)
-                .await?;
-            println!("{}", serde_json::to_string(&output)?);
+            let value = match block {
+                Some(block) => {
+                    node_client
+                        .query_state_at(content_address.to_owned(), key, block)
+                        .await?
+                }
+                None => {
+                    node_client
+                        .query_state(content_address.to_owned(), key)
+                        .await?
+                }
+            };
+            Output::State(value)
+        }
+        Command::GetContract {
+            node_address,
+            content_address,
+            big_bang,
+        } => {
+            let big_bang = big_bang_or_default(big_bang.as_deref()).await?;
+            let node_client = EssentialNodeClient::new(node_address)?;
+            let Some(contract) = node_client
+                .get_contract(&big_bang.contract_registry, &content_address)
+                .await?
+            else {
+                anyhow::bail!("Contract {content_address} is not registered");
+            };
+            // Each program once, in the order the contract's predicates first use them.
+            let mut program_cas: Vec<&ContentAddress> = vec![];
+            for node in contract.predicates.iter().flat_map(|p| &p.nodes) {
+                if !program_cas.contains(&&node.program_address) {
+                    program_cas.push(&node.program_address);
+                }
+            }
+            let mut programs = vec![];
+            for program_ca in program_cas {
+                match node_client
+                    .get_program(&big_bang.program_registry, program_ca)
+                    .await?
+                {
+                    Some(program) => programs.push(program),
+                    None => anyhow::bail!("Program {program_ca} is not registered"),
+                }
+            }
+            Output::Contract((contract, programs))
+        }
+        Command::GetProgram {
+            node_address,
+            content_address,
+            big_bang,
+        } => {
+            let big_bang = big_bang_or_default(big_bang.as_deref()).await?;
+            let node_client = EssentialNodeClient::new(node_address)?;
+            match node_client
+                .get_program(&big_bang.program_registry, &content_address)
+                .await?
+            {
+                Some(program) => Output::Program(program),
+                None => anyhow::bail!("Program {content_address} is not registered"),
+            }
         }
         Command::RegisterContract {
             builder_address,
             contract,
+            big_bang,
         } => {
-            // FIXME: Allow for specifying big bang config via CLI argument & handle fallback to
-            // default.
-            let big_bang = BigBang::default();
-            let builder_client = EssentialBuilderClient::new(builder_address)?;
+            let big_bang = big_bang_or_default(big_bang.as_deref()).await?;
+            let builder_client = builder_client(builder_address, format)?;
             let (contract, programs) = contract_from_path(&contract).await?;
-            let output = builder_client
+            let solution_set = builder_client
                 .register_contract(
                     &big_bang.contract_registry,
                     &big_bang.program_registry,
@@ -110,31 +304,72 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
                     &programs,
                 )
                 .await?;
-            println!("{}", output);
+            Output::RegisteredContract {
+                contract: essential_hash::content_addr(&contract),
+                solution_set,
+            }
         }
         Command::SubmitSolutionSet {
             builder_address,
             solution_set,
+            #[cfg(feature = "dry-run")]
+            dry_run,
+            #[cfg(feature = "dry-run")]
+            node_address,
+            #[cfg(feature = "dry-run")]
+            big_bang,
         } => {
-            let builder_client = EssentialBuilderClient::new(builder_address)?;
+            let builder_client = builder_client(builder_address, format)?;
             let solution_set =
                 serde_json::from_str::<SolutionSet>(&from_file(solution_set).await?)?;
-            let output = builder_client.submit_solution_set(&solution_set).await?;
-            println!("{}", output);
+            #[cfg(feature = "dry-run")]
+            if let Some(node_address) = node_address.filter(|_| dry_run) {
+                let big_bang = big_bang_or_default(big_bang.as_deref()).await?;
+                let node_client = EssentialNodeClient::new(node_address)?;
+                return dry_run_solution_set(&node_client, &big_bang, solution_set).await;
+            }
+            let solution_set = builder_client.submit_solution_set(&solution_set).await?;
+            Output::SubmittedSolutionSet { solution_set }
         }
         Command::LatestSolutionFailures {
             builder_address,
             content_address,
             limit,
         } => {
-            let builder_client = EssentialBuilderClient::new(builder_address)?;
-            let output = builder_client
-                .latest_solution_failures(&content_address, limit)
-                .await?;
-            println!("{}", serde_json::to_string(&output)?);
+            let builder_client = builder_client(builder_address, format)?;
+            Output::Failures(
+                builder_client
+                    .latest_solution_failures(&content_address, limit)
+                    .await?,
+            )
         }
+    };
+    Ok(output)
+}
+
+/// Validate the solution set against a local snapshot of the node.
+#[cfg(feature = "dry-run")]
+async fn dry_run_solution_set(
+    node_client: &EssentialNodeClient,
+    big_bang: &essential_node_types::BigBang,
+    solution_set: SolutionSet,
+) -> anyhow::Result<Output> {
+    use essential_rest_client::{dry_run::DryRunOutcome, exit::ValidationError};
+    let outcome =
+        essential_rest_client::dry_run::dry_run(node_client, big_bang, solution_set).await?;
+    if let DryRunOutcome::Invalid { .. } = outcome {
+        return Err(ValidationError(format!("Solution set {outcome}")).into());
     }
-    Ok(())
+    Ok(Output::DryRun(outcome))
+}
+
+/// Create a builder client that prints progress in the human readable format.
+fn builder_client(address: String, format: Format) -> anyhow::Result<EssentialBuilderClient> {
+    let client = EssentialBuilderClient::new(address)?;
+    Ok(match format {
+        Format::Human => client.with_progress(print_progress),
+        Format::Json | Format::Quiet => client,
+    })
 }
 
 async fn from_file(path: PathBuf) -> anyhow::Result<String> {
@@ -142,6 +377,94 @@ async fn from_file(path: PathBuf) -> anyhow::Result<String> {
     Ok(content)
 }
 
+/// Write the value as pretty JSON, so it can be saved and read back as a file.
+fn json(f: &mut fmt::Formatter<'_>, value: &impl Serialize) -> fmt::Result {
+    let json = serde_json::to_string_pretty(value).map_err(|_| fmt::Error)?;
+    write!(f, "{json}")
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Output::NodeInfo(info) => {
+                writeln!(f, "Node is healthy (latency {:?})", info.latency)?;
+                match info.latest_block_number {
+                    Some(number) => writeln!(f, "Latest block: {number}")?,
+                    None => writeln!(f, "Latest block: none")?,
+                }
+                match &info.big_bang_block {
+                    Some(ca) => write!(f, "Big bang block: {ca}"),
+                    None => write!(f, "Big bang block: none"),
+                }
+            }
+            Output::BuilderInfo(info) => {
+                write!(f, "Builder is healthy (latency {:?})", info.latency)
+            }
+            Output::Blocks(blocks) if blocks.is_empty() => write!(f, "No blocks in range"),
+            Output::Blocks(blocks) => {
+                for (i, block) in blocks.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(
+                        f,
+                        "Block {} at {}s with {} solution set(s)",
+                        block.header.number,
+                        block.header.timestamp.as_secs(),
+                        block.solution_sets.len(),
+                    )?;
+                    for set in &block.solution_sets {
+                        write!(f, "\n  {}", essential_hash::content_addr(set))?;
+                    }
+                }
+                Ok(())
+            }
+            Output::State(Some(value)) if value.is_empty() => write!(f, "Empty value"),
+            Output::State(Some(value)) => {
+                for (i, word) in value.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{word}")?;
+                }
+                Ok(())
+            }
+            Output::State(None) => write!(f, "No value"),
+            Output::RegisteredContract {
+                contract,
+                solution_set,
+            } => write!(
+                f,
+                "Registered contract {contract} in solution set {solution_set}"
+            ),
+            Output::SubmittedSolutionSet { solution_set } => {
+                write!(f, "Submitted solution set {solution_set}")
+            }
+            #[cfg(feature = "dry-run")]
+            Output::DryRun(outcome) => write!(f, "Solution set {outcome}"),
+            Output::Contract(contract) => json(f, contract),
+            Output::Program(program) => json(f, program),
+            Output::Failures(failures) if failures.is_empty() => write!(f, "No failures"),
+            Output::Failures(failures) => {
+                for (i, failure) in failures.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(
+                        f,
+                        "Block {} ({}) at index {}: {}",
+                        failure.attempt_block_num,
+                        failure.attempt_block_addr,
+                        failure.attempt_solution_set_ix,
+                        failure.err_msg,
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct BlockRange {
     start: Word,
@@ -1,8 +1,23 @@
-use crate::handle_response;
-use essential_node_types::Block;
-use essential_types::{convert::bytes_from_word, ContentAddress, Key, Value, Word};
-use reqwest::{Client, ClientBuilder};
-use std::ops::Range;
+use crate::{
+    block_stream::BlockSubscription, config::ClientConfig, decode_json, error::ClientError, send,
+};
+use essential_node_types::{contract_registry, program_registry, BigBang, Block};
+use essential_types::{
+    convert::{bytes_from_word, u8_32_from_word_4},
+    predicate::Predicate,
+    ContentAddress, Contract, Key, PredicateAddress, Program, Value, Word,
+};
+use futures::{Stream, StreamExt, TryStreamExt};
+use reqwest::Client;
+use serde::Serialize;
+use std::{
+    collections::{BTreeSet, HashSet},
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+/// The maximum number of blocks requested at once when walking the chain.
+pub(crate) const BLOCK_PAGE_SIZE: Word = 64;
 
 /// Client that binds to an Essential node address.
 #[derive(Clone)]
@@ -11,27 +26,118 @@ pub struct EssentialNodeClient {
     client: Client,
     /// The url to make requests to.
     url: reqwest::Url,
+    /// Timeout and retry configuration applied to each request.
+    config: ClientConfig,
+}
+
+/// Information about a node, as returned by [`EssentialNodeClient::info`].
+///
+/// Everything here is read from the node. The node API does not report its version.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct NodeInfo {
+    /// The round trip time of the node's health check.
+    pub latency: Duration,
+    /// The number of the latest block known to the node, or `None` if it has no blocks.
+    pub latest_block_number: Option<Word>,
+    /// The content address of the node's big bang block, or `None` if it has no blocks.
+    pub big_bang_block: Option<ContentAddress>,
+}
+
+impl NodeInfo {
+    /// Whether or not the node was initialized with the given big bang configuration.
+    pub fn uses_big_bang(&self, big_bang: &BigBang) -> bool {
+        let expected = essential_node_types::block::addr::from_block(&big_bang.block());
+        self.big_bang_block.as_ref() == Some(&expected)
+    }
 }
 
 impl EssentialNodeClient {
-    /// Create a new client with the given address.
-    pub fn new(addr: String) -> anyhow::Result<Self> {
-        let client = ClientBuilder::new().http2_prior_knowledge().build()?;
+    /// Create a new client with the given address and the default [`ClientConfig`].
+    pub fn new(addr: String) -> Result<Self, ClientError> {
+        Self::with_config(addr, ClientConfig::default())
+    }
+
+    /// Create a new client with the given address and configuration.
+    pub fn with_config(addr: String, config: ClientConfig) -> Result<Self, ClientError> {
+        let client = config.build_client()?;
         let url = reqwest::Url::parse(&addr)?;
-        Ok(Self { client, url })
+        Ok(Self {
+            client,
+            url,
+            config,
+        })
+    }
+
+    /// The configuration used by this client.
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// The underlying `reqwest` client.
+    pub(crate) fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// The url requests are made to.
+    pub(crate) fn url(&self) -> &reqwest::Url {
+        &self.url
+    }
+
+    /// Check that the node is reachable and healthy.
+    pub async fn health_check(&self) -> Result<(), ClientError> {
+        let url = self.url.join("/")?;
+        send(&self.config, self.client.get(url)).await?;
+        Ok(())
+    }
+
+    /// Get information about the node, checking that it is reachable along the way.
+    pub async fn info(&self) -> Result<NodeInfo, ClientError> {
+        let start = Instant::now();
+        self.health_check().await?;
+        let latency = start.elapsed();
+        let latest_block_number = self.latest_block_number().await?;
+        let big_bang_block = self
+            .list_blocks(0..1)
+            .await?
+            .first()
+            .map(essential_node_types::block::addr::from_block);
+        Ok(NodeInfo {
+            latency,
+            latest_block_number,
+            big_bang_block,
+        })
     }
 
     /// List blocks in the given L2 block number range.
     ///
     /// Blocks are only created if there are valid solutions.
     /// Blocks are created on a regular interval.
-    pub async fn list_blocks(&self, range: Range<Word>) -> anyhow::Result<Vec<Block>> {
+    pub async fn list_blocks(&self, range: Range<Word>) -> Result<Vec<Block>, ClientError> {
         let url = self.url.join(&format!(
             "/list-blocks?start={}&end={}",
             range.start, range.end
         ))?;
-        let response = handle_response(self.client.get(url).send().await?).await?;
-        Ok(response.json::<Vec<Block>>().await?)
+        let response = send(&self.config, self.client.get(url)).await?;
+        decode_json(response).await
+    }
+
+    /// Subscribe to blocks starting from the given L2 block number.
+    ///
+    /// Yields each block in order, waiting for new blocks once the head is reached.
+    /// Uses the node's `/subscribe-blocks` streaming endpoint, falling back to polling
+    /// `list-blocks` at the configured poll interval if the node does not provide it.
+    ///
+    /// The stream never ends on its own. Errors are yielded without ending the stream,
+    /// and polling the stream again resumes from the block following the last one yielded.
+    pub fn subscribe_blocks(
+        &self,
+        start: Word,
+    ) -> impl Stream<Item = Result<Block, ClientError>> + Send + 'static {
+        let subscription = BlockSubscription::new(self.clone(), start);
+        futures::stream::unfold(subscription, |mut subscription| async move {
+            let next = subscription.next_block().await;
+            Some((next, subscription))
+        })
     }
 
     /// Query state in the given contract address and key.
@@ -45,13 +151,333 @@ impl EssentialNodeClient {
         &self,
         contract_ca: ContentAddress,
         key: Key,
-    ) -> anyhow::Result<Option<Value>> {
+    ) -> Result<Option<Value>, ClientError> {
+        let url = self.query_state_url(&contract_ca, &key)?;
+        let response = send(&self.config, self.client.get(url)).await?;
+        decode_json(response).await
+    }
+
+    /// The URL of the `query-state` endpoint for the given contract address and key.
+    fn query_state_url(
+        &self,
+        contract_ca: &ContentAddress,
+        key: &[Word],
+    ) -> Result<reqwest::Url, ClientError> {
         let key_bytes: Vec<_> = key.iter().copied().flat_map(bytes_from_word).collect();
         let key = hex::encode(&key_bytes);
-        let url = self
+        Ok(self
             .url
-            .join(&format!("/query-state/{contract_ca}/{key}"))?;
-        let response = handle_response(self.client.get(url).send().await?).await?;
-        Ok(response.json::<Option<Value>>().await?)
+            .join(&format!("/query-state/{contract_ca}/{key}"))?)
+    }
+
+    /// Query state at each of the given keys in the given contract address.
+    ///
+    /// Queries are made concurrently, bounded by [`ClientConfig::max_concurrent_requests`].
+    /// Values are returned in the same order as the given keys.
+    pub async fn query_states(
+        &self,
+        contract_ca: ContentAddress,
+        keys: &[Key],
+    ) -> Result<Vec<Option<Value>>, ClientError> {
+        futures::stream::iter(keys)
+            .map(|key| self.query_state(contract_ca.clone(), key.clone()))
+            .buffered(self.config.max_concurrent_requests())
+            .try_collect()
+            .await
+    }
+
+    /// Query state at every key formed by appending each word in the given range to `prefix`.
+    ///
+    /// Useful for reading the fields of a tuple or the elements of an array in storage.
+    /// Returns the key and value for each key in the range, in order.
+    pub async fn query_state_range(
+        &self,
+        contract_ca: ContentAddress,
+        prefix: &[Word],
+        range: Range<Word>,
+    ) -> Result<Vec<(Key, Option<Value>)>, ClientError> {
+        let keys: Vec<Key> = range
+            .map(|word| prefix.iter().copied().chain(Some(word)).collect())
+            .collect();
+        let values = self.query_states(contract_ca, &keys).await?;
+        Ok(keys.into_iter().zip(values).collect())
+    }
+
+    /// Query state at every key in the given contract address that starts with `prefix`.
+    ///
+    /// The node cannot be queried by key prefix, so the set of keys is discovered by walking
+    /// all blocks for mutations to the contract. This is proportional to the length of the
+    /// chain regardless of the prefix, and is intended for reading maps whose keys are not
+    /// known ahead of time. Prefer [`Self::query_states`] when the keys are known. An empty
+    /// prefix matches every key of the contract.
+    ///
+    /// Returns the key and value of each non-empty entry, ordered by key.
+    pub async fn query_state_prefix(
+        &self,
+        contract_ca: ContentAddress,
+        prefix: &[Word],
+    ) -> Result<Vec<(Key, Value)>, ClientError> {
+        let keys: BTreeSet<Key> = self
+            .mutated_keys(&contract_ca, prefix)
+            .await?
+            .into_iter()
+            .collect();
+        let keys: Vec<Key> = keys.into_iter().collect();
+        let values = self.query_states(contract_ca, &keys).await?;
+        Ok(keys
+            .into_iter()
+            .zip(values)
+            .filter_map(|(key, value)| Some((key, value.filter(|v| !v.is_empty())?)))
+            .collect())
     }
+
+    /// Every key in the given contract address that starts with `prefix` and was mutated in a
+    /// block, in the order in which each key was first mutated.
+    ///
+    /// This walks all blocks, as in [`Self::query_state_prefix`].
+    async fn mutated_keys(
+        &self,
+        contract_ca: &ContentAddress,
+        prefix: &[Word],
+    ) -> Result<Vec<Key>, ClientError> {
+        let mut seen = HashSet::new();
+        let mut keys = vec![];
+        let mut next = 0;
+        loop {
+            let blocks = self
+                .list_blocks(next..next.saturating_add(BLOCK_PAGE_SIZE))
+                .await?;
+            let mutated = blocks
+                .iter()
+                .flat_map(|block| &block.solution_sets)
+                .flat_map(|set| &set.solutions)
+                .filter(|solution| solution.predicate_to_solve.contract == *contract_ca)
+                .flat_map(|solution| &solution.state_mutations)
+                .filter(|mutation| mutation.key.starts_with(prefix))
+                .filter(|mutation| seen.insert(mutation.key.clone()))
+                .map(|mutation| mutation.key.clone());
+            keys.extend(mutated);
+            match blocks.last() {
+                Some(block) if (blocks.len() as Word) == BLOCK_PAGE_SIZE => {
+                    next = block.header.number.saturating_add(1);
+                }
+                _ => break,
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Query state in the given contract address and key as of the end of the given block.
+    ///
+    /// Returns `None` if the key was never set or was deleted as of the given block.
+    ///
+    /// The node finds the value by searching the mutations of its finalized blocks for the
+    /// latest one to the key, so each query costs the node time proportional to the number
+    /// of mutations in the chain, however recent the given block.
+    pub async fn query_state_at(
+        &self,
+        contract_ca: ContentAddress,
+        key: Key,
+        block_number: Word,
+    ) -> Result<Option<Value>, ClientError> {
+        let mut url = self.query_state_url(&contract_ca, &key)?;
+        url.query_pairs_mut()
+            .append_pair("block_inclusive", &block_number.to_string());
+        let response = send(&self.config, self.client.get(url)).await?;
+        let value: Option<Value> = decode_json(response).await?;
+        Ok(value.filter(|v| !v.is_empty()))
+    }
+
+    /// Query state at each of the given keys as of the end of the given block.
+    ///
+    /// All keys are read from the same block, giving a consistent view of state across keys.
+    /// Queries are made concurrently as in [`Self::query_states`], and each has the cost
+    /// described in [`Self::query_state_at`].
+    /// Values are returned in the same order as the given keys.
+    pub async fn query_states_at(
+        &self,
+        contract_ca: ContentAddress,
+        keys: &[Key],
+        block_number: Word,
+    ) -> Result<Vec<Option<Value>>, ClientError> {
+        futures::stream::iter(keys)
+            .map(|key| self.query_state_at(contract_ca.clone(), key.clone(), block_number))
+            .buffered(self.config.max_concurrent_requests())
+            .try_collect()
+            .await
+    }
+
+    /// Whether the contract with the given content address is registered in the given
+    /// contract registry.
+    ///
+    /// A contract is registered once its salt is set in the registry.
+    pub async fn is_contract_registered(
+        &self,
+        contract_registry: &PredicateAddress,
+        contract_ca: &ContentAddress,
+    ) -> Result<bool, ClientError> {
+        let key = contract_registry::contract_salt_key(contract_ca);
+        let value = self
+            .query_state(contract_registry.contract.clone(), key)
+            .await?;
+        Ok(value.is_some_and(|v| !v.is_empty()))
+    }
+
+    /// Whether the program with the given content address is registered in the given
+    /// program registry.
+    pub async fn is_program_registered(
+        &self,
+        program_registry: &PredicateAddress,
+        program_ca: &ContentAddress,
+    ) -> Result<bool, ClientError> {
+        let key = program_registry::program_key(program_ca);
+        let value = self
+            .query_state(program_registry.contract.clone(), key)
+            .await?;
+        Ok(value.is_some_and(|v| !v.is_empty()))
+    }
+
+    /// Fetch the contract with the given content address from the given contract registry.
+    ///
+    /// The registry cannot be queried for a contract's predicates directly, so they are
+    /// found by walking all blocks as in [`Self::query_state_prefix`]. The registry does not
+    /// record the order of the predicates, and the contract's address does not depend on it,
+    /// so the predicates are returned in the order their registration mutated them, which is
+    /// the order of the registered contract.
+    ///
+    /// Returns `None` if the contract is not registered.
+    pub async fn get_contract(
+        &self,
+        contract_registry: &PredicateAddress,
+        contract_ca: &ContentAddress,
+    ) -> Result<Option<Contract>, ClientError> {
+        let registry = &contract_registry.contract;
+        let salt_key = contract_registry::contract_salt_key(contract_ca);
+        let salt = match self.query_state(registry.clone(), salt_key.clone()).await? {
+            Some(salt) if !salt.is_empty() => salt,
+            _ => return Ok(None),
+        };
+        let salt: [Word; 4] = salt.try_into().map_err(|salt: Vec<Word>| {
+            ClientError::Registry(format!(
+                "expected a 4 word salt, found {} words",
+                salt.len()
+            ))
+        })?;
+
+        // Every key under the contract other than the salt marks one of its predicates.
+        let prefix = &salt_key[..salt_key.len() - 1];
+        let predicate_cas = self
+            .mutated_keys(registry, prefix)
+            .await?
+            .into_iter()
+            .filter_map(|key| {
+                let words: [Word; 4] = key.get(prefix.len()..)?.try_into().ok()?;
+                Some(ContentAddress(u8_32_from_word_4(words)))
+            })
+            .collect::<Vec<_>>();
+        let keys: Vec<Key> = predicate_cas
+            .iter()
+            .map(contract_registry::predicate_key)
+            .collect();
+        let values = self.query_states(registry.clone(), &keys).await?;
+        let mut predicates = vec![];
+        for (predicate_ca, value) in predicate_cas.iter().zip(values) {
+            let value = value.filter(|v| !v.is_empty()).ok_or_else(|| {
+                ClientError::Registry(format!("predicate {predicate_ca} is not registered"))
+            })?;
+            let bytes = registry_bytes(&value)?;
+            let predicate = Predicate::decode(&bytes).map_err(|err| {
+                ClientError::Registry(format!("failed to decode predicate {predicate_ca}: {err}"))
+            })?;
+            predicates.push(predicate);
+        }
+
+        let contract = Contract {
+            predicates,
+            salt: u8_32_from_word_4(salt),
+        };
+        let received = essential_hash::content_addr(&contract);
+        if &received != contract_ca {
+            return Err(ClientError::Registry(format!(
+                "reconstructed contract has address {received}, expected {contract_ca}"
+            )));
+        }
+        Ok(Some(contract))
+    }
+
+    /// Fetch the program with the given content address from the given program registry.
+    ///
+    /// Returns `None` if the program is not registered.
+    pub async fn get_program(
+        &self,
+        program_registry: &PredicateAddress,
+        program_ca: &ContentAddress,
+    ) -> Result<Option<Program>, ClientError> {
+        let key = program_registry::program_key(program_ca);
+        let value = match self
+            .query_state(program_registry.contract.clone(), key)
+            .await?
+        {
+            Some(value) if !value.is_empty() => value,
+            _ => return Ok(None),
+        };
+        let program = Program(registry_bytes(&value)?);
+        let received = essential_hash::content_addr(&program);
+        if &received != program_ca {
+            return Err(ClientError::Registry(format!(
+                "program has address {received}, expected {program_ca}"
+            )));
+        }
+        Ok(Some(program))
+    }
+
+    /// Find the number of the latest block known to the node.
+    ///
+    /// Returns `None` if the node has no blocks.
+    pub(crate) async fn latest_block_number(&self) -> Result<Option<Word>, ClientError> {
+        if !self.has_block(0).await? {
+            return Ok(None);
+        }
+        // Gallop forward to find an upper bound, then binary search for the head.
+        let (mut lo, mut hi) = (0, 1);
+        while self.has_block(hi).await? {
+            lo = hi;
+            hi = hi.saturating_mul(2);
+        }
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if self.has_block(mid).await? {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        Ok(Some(lo))
+    }
+
+    /// Whether or not the node has a block with the given number.
+    async fn has_block(&self, number: Word) -> Result<bool, ClientError> {
+        let blocks = self.list_blocks(number..number.saturating_add(1)).await?;
+        Ok(!blocks.is_empty())
+    }
+}
+
+/// The bytes of a predicate or program stored in a registry, where the first word is the
+/// length in bytes and the remaining words are the zero padded bytes.
+fn registry_bytes(value: &[Word]) -> Result<Vec<u8>, ClientError> {
+    let (len, words) = value
+        .split_first()
+        .ok_or_else(|| ClientError::Registry("missing length".to_string()))?;
+    let mut bytes: Vec<u8> = words.iter().copied().flat_map(bytes_from_word).collect();
+    let len = usize::try_from(*len)
+        .ok()
+        .filter(|len| *len <= bytes.len())
+        .ok_or_else(|| {
+            ClientError::Registry(format!(
+                "length {len} exceeds the {} stored bytes",
+                bytes.len()
+            ))
+        })?;
+    bytes.truncate(len);
+    Ok(bytes)
 }
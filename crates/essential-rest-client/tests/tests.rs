@@ -1,6 +1,31 @@
-use essential_rest_client::node_client::EssentialNodeClient;
-use essential_types::{ContentAddress, Key};
-use utils::setup_node_as_server;
+use essential_node_types::{
+    register_contract_mutations, register_program_mutations, BigBang, Block, BlockHeader,
+};
+use essential_rest_client::{
+    big_bang_from_path, big_bang_or_default,
+    builder_client::{register_contract_solution_set, EssentialBuilderClient},
+    config::{ClientConfig, ClientConfigBuilder},
+    error::ClientError,
+    node_client::EssentialNodeClient,
+};
+use essential_types::{
+    contract::Contract,
+    predicate::{Edge, Node, Predicate, Reads},
+    solution::{Mutation, Solution, SolutionSet},
+    ContentAddress, Key, PredicateAddress, Program, Word,
+};
+use futures::{Stream, StreamExt, TryStreamExt};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use utils::{
+    serve_router, setup_node_as_server, setup_node_as_server_with_block_tx,
+    setup_node_as_server_with_db,
+};
 
 mod utils;
 
@@ -16,3 +41,546 @@ async fn test_api() {
 
     assert_eq!(r, None);
 }
+
+#[tokio::test]
+async fn test_big_bang_yaml_round_trip() {
+    let mut big_bang = BigBang::default();
+    big_bang.contract_registry.contract = ContentAddress([1u8; 32]);
+    big_bang.program_registry.predicate = ContentAddress([2u8; 32]);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("big-bang.yml");
+    std::fs::write(&path, serde_yaml::to_string(&big_bang).unwrap()).unwrap();
+
+    assert_eq!(big_bang_from_path(&path).await.unwrap(), big_bang);
+    assert_eq!(big_bang_or_default(Some(&path)).await.unwrap(), big_bang);
+    assert_eq!(big_bang_or_default(None).await.unwrap(), BigBang::default());
+
+    std::fs::write(&path, "contract_registry: [").unwrap();
+    assert!(big_bang_from_path(&path).await.is_err());
+    assert!(big_bang_from_path(&dir.path().join("missing.yml"))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_http_error() {
+    let router = axum::Router::new().route(
+        "/submit-solution-set",
+        axum::routing::post(|| async {
+            (axum::http::StatusCode::BAD_REQUEST, "invalid solution set")
+        }),
+    );
+    let addr = serve_router(router).await;
+    let client = EssentialBuilderClient::new(addr).unwrap();
+
+    let err = client
+        .submit_solution_set(&SolutionSet { solutions: vec![] })
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(reqwest::StatusCode::BAD_REQUEST));
+    match err {
+        ClientError::Http { body, .. } => assert_eq!(body, "invalid solution set"),
+        err => panic!("expected an HTTP error, found {err:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_content_address_mismatch() {
+    let received = ContentAddress([7u8; 32]);
+    let response = received.clone();
+    let router = axum::Router::new().route(
+        "/submit-solution-set",
+        axum::routing::post(move || async move { axum::Json(response) }),
+    );
+    let addr = serve_router(router).await;
+    let client = EssentialBuilderClient::new(addr).unwrap();
+
+    let solution_set = SolutionSet { solutions: vec![] };
+    let err = client.submit_solution_set(&solution_set).await.unwrap_err();
+    assert_eq!(err.status(), None);
+    match err {
+        ClientError::ContentAddressMismatch {
+            expected,
+            received: r,
+        } => {
+            assert_eq!(expected, essential_hash::content_addr(&solution_set));
+            assert_eq!(r, received);
+        }
+        err => panic!("expected a content address mismatch, found {err:?}"),
+    }
+}
+
+/// A router that responds with `503 Service Unavailable` to the first `failures` requests
+/// on the given route, and with `response` after that, along with its request count.
+fn flaky_router(
+    path: &str,
+    failures: usize,
+    response: serde_json::Value,
+) -> (axum::Router, Arc<AtomicUsize>) {
+    let count = Arc::new(AtomicUsize::new(0));
+    let handler = {
+        let count = count.clone();
+        move || async move {
+            use axum::response::IntoResponse;
+            if count.fetch_add(1, Ordering::SeqCst) < failures {
+                axum::http::StatusCode::SERVICE_UNAVAILABLE.into_response()
+            } else {
+                axum::Json(response).into_response()
+            }
+        }
+    };
+    let router = axum::Router::new().route(path, axum::routing::any(handler));
+    (router, count)
+}
+
+fn fast_retries(max_retries: u32) -> ClientConfigBuilder {
+    ClientConfig::builder()
+        .max_retries(max_retries)
+        .initial_backoff(Duration::from_millis(1))
+        .max_backoff(Duration::from_millis(4))
+}
+
+#[tokio::test]
+async fn test_retry_transient_status() {
+    let (router, count) = flaky_router("/query-state/:contract/:key", 2, serde_json::json!([1]));
+    let addr = serve_router(router).await;
+    let client = EssentialNodeClient::with_config(addr, fast_retries(3).build()).unwrap();
+
+    let r = client
+        .query_state(ContentAddress([42u8; 32]), vec![0])
+        .await
+        .unwrap();
+    assert_eq!(r, Some(vec![1]));
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_retries_exhausted() {
+    let (router, count) = flaky_router(
+        "/query-state/:contract/:key",
+        usize::MAX,
+        serde_json::Value::Null,
+    );
+    let addr = serve_router(router).await;
+    let client = EssentialNodeClient::with_config(addr, fast_retries(2).build()).unwrap();
+
+    let err = client
+        .query_state(ContentAddress([42u8; 32]), vec![0])
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(reqwest::StatusCode::SERVICE_UNAVAILABLE));
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_submissions_retried_only_when_enabled() {
+    let solution_set = SolutionSet { solutions: vec![] };
+    let ca = serde_json::to_value(essential_hash::content_addr(&solution_set)).unwrap();
+
+    let (router, count) = flaky_router("/submit-solution-set", 1, ca.clone());
+    let addr = serve_router(router).await;
+    let client = EssentialBuilderClient::with_config(addr, fast_retries(3).build()).unwrap();
+    let err = client.submit_solution_set(&solution_set).await.unwrap_err();
+    assert_eq!(err.status(), Some(reqwest::StatusCode::SERVICE_UNAVAILABLE));
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+
+    let (router, count) = flaky_router("/submit-solution-set", 1, ca);
+    let addr = serve_router(router).await;
+    let config = fast_retries(3).retry_submissions(true).build();
+    let client = EssentialBuilderClient::with_config(addr, config).unwrap();
+    client.submit_solution_set(&solution_set).await.unwrap();
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_query_states() {
+    let (addr, db) = setup_node_as_server_with_db().await.unwrap();
+    let contract_ca = ContentAddress([42u8; 32]);
+    let mutations = vec![
+        Mutation {
+            key: vec![1, 0],
+            value: vec![10],
+        },
+        Mutation {
+            key: vec![1, 2],
+            value: vec![12],
+        },
+        Mutation {
+            key: vec![2, 0],
+            value: vec![20],
+        },
+    ];
+    // The node serves state from the mutations in finalized blocks.
+    let block = Block {
+        header: BlockHeader {
+            number: 0,
+            timestamp: Duration::from_secs(0),
+        },
+        solution_sets: vec![SolutionSet {
+            solutions: vec![Solution {
+                predicate_to_solve: PredicateAddress {
+                    contract: contract_ca.clone(),
+                    predicate: ContentAddress([0u8; 32]),
+                },
+                predicate_data: vec![],
+                state_mutations: mutations,
+            }],
+        }],
+    };
+    let block_ca = db.insert_block(Arc::new(block)).await.unwrap();
+    db.finalize_block(block_ca).await.unwrap();
+    let client = EssentialNodeClient::new(addr).unwrap();
+
+    let r = client
+        .query_states(contract_ca.clone(), &[vec![2, 0], vec![3], vec![1, 0]])
+        .await
+        .unwrap();
+    assert_eq!(r, vec![Some(vec![20]), None, Some(vec![10])]);
+
+    let r = client
+        .query_state_range(contract_ca.clone(), &[1], 0..3)
+        .await
+        .unwrap();
+    assert_eq!(
+        r,
+        vec![
+            (vec![1, 0], Some(vec![10])),
+            (vec![1, 1], None),
+            (vec![1, 2], Some(vec![12])),
+        ]
+    );
+
+    let r = client
+        .query_state_prefix(contract_ca.clone(), &[1])
+        .await
+        .unwrap();
+    assert_eq!(r, vec![(vec![1, 0], vec![10]), (vec![1, 2], vec![12])]);
+    let r = client.query_state_prefix(contract_ca, &[3]).await.unwrap();
+    assert!(r.is_empty());
+}
+
+/// A block with the given number and a single empty solution.
+///
+/// The node can only list blocks that contain at least one solution.
+fn test_block(number: Word) -> Block {
+    Block {
+        header: BlockHeader {
+            number,
+            timestamp: Duration::from_secs(number as u64),
+        },
+        solution_sets: vec![SolutionSet {
+            solutions: vec![Solution {
+                predicate_to_solve: PredicateAddress {
+                    contract: ContentAddress([42u8; 32]),
+                    predicate: ContentAddress([0u8; 32]),
+                },
+                predicate_data: vec![],
+                state_mutations: vec![],
+            }],
+        }],
+    }
+}
+
+#[tokio::test]
+async fn test_subscribe_blocks() {
+    let (addr, db) = setup_node_as_server_with_db().await.unwrap();
+    for number in 0..3 {
+        let block_ca = db.insert_block(Arc::new(test_block(number))).await.unwrap();
+        db.finalize_block(block_ca).await.unwrap();
+    }
+    let client = EssentialNodeClient::new(addr).unwrap();
+
+    let blocks: Vec<_> = tokio::time::timeout(
+        Duration::from_secs(10),
+        client.subscribe_blocks(1).take(2).try_collect(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    let numbers: Vec<_> = blocks.iter().map(|b| b.header.number).collect();
+    assert_eq!(numbers, vec![1, 2]);
+}
+
+/// The next block in the given subscription, failing if it takes too long.
+async fn next_block(
+    blocks: &mut (impl Stream<Item = Result<Block, ClientError>> + Unpin),
+) -> Block {
+    tokio::time::timeout(Duration::from_secs(10), blocks.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_subscribe_new_blocks() {
+    let (addr, db, block_tx) = setup_node_as_server_with_block_tx().await.unwrap();
+    let block_ca = db.insert_block(Arc::new(test_block(0))).await.unwrap();
+    db.finalize_block(block_ca).await.unwrap();
+    let client = EssentialNodeClient::new(addr).unwrap();
+
+    let mut blocks = Box::pin(client.subscribe_blocks(0));
+    let block = next_block(&mut blocks).await;
+    assert_eq!(block, test_block(0));
+
+    // Blocks created after subscribing are streamed as the node is notified of them.
+    let produce = async {
+        for number in 1..3 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let block_ca = db.insert_block(Arc::new(test_block(number))).await.unwrap();
+            db.finalize_block(block_ca).await.unwrap();
+            block_tx.notify();
+        }
+    };
+    let consume = async {
+        let mut received = vec![];
+        for _ in 1..3 {
+            received.push(next_block(&mut blocks).await);
+        }
+        received
+    };
+    let ((), received) = tokio::join!(produce, consume);
+    assert_eq!(received, vec![test_block(1), test_block(2)]);
+}
+
+#[tokio::test]
+async fn test_query_state_at() {
+    let (addr, db) = setup_node_as_server_with_db().await.unwrap();
+    let contract_ca = ContentAddress([42u8; 32]);
+    let key: Key = vec![1];
+    for (number, value) in [vec![10], vec![20], vec![]].into_iter().enumerate() {
+        let solution = Solution {
+            predicate_to_solve: PredicateAddress {
+                contract: contract_ca.clone(),
+                predicate: ContentAddress([0u8; 32]),
+            },
+            predicate_data: vec![],
+            state_mutations: vec![Mutation {
+                key: key.clone(),
+                value,
+            }],
+        };
+        let block = Block {
+            header: BlockHeader {
+                number: number as i64,
+                timestamp: Duration::from_secs(number as u64),
+            },
+            solution_sets: vec![SolutionSet {
+                solutions: vec![solution],
+            }],
+        };
+        let block_ca = db.insert_block(Arc::new(block)).await.unwrap();
+        db.finalize_block(block_ca).await.unwrap();
+    }
+    let client = EssentialNodeClient::new(addr).unwrap();
+
+    let r = client
+        .query_state_at(contract_ca.clone(), key.clone(), 0)
+        .await
+        .unwrap();
+    assert_eq!(r, Some(vec![10]));
+    let r = client
+        .query_states_at(contract_ca, &[key.clone(), vec![2]], 1)
+        .await
+        .unwrap();
+    assert_eq!(r, vec![Some(vec![20]), None]);
+    let r = client
+        .query_state_at(ContentAddress([42u8; 32]), key, 2)
+        .await
+        .unwrap();
+    assert_eq!(r, None);
+}
+
+#[tokio::test]
+async fn test_node_info() {
+    let addr = setup_node_as_server().await.unwrap();
+    let client = EssentialNodeClient::new(addr).unwrap();
+
+    client.health_check().await.unwrap();
+    let info = client.info().await.unwrap();
+
+    assert_eq!(info.latest_block_number, None);
+    assert_eq!(info.big_bang_block, None);
+}
+
+/// Insert and finalize a block solving the given registry predicate with the given mutations.
+///
+/// The node serves state from the mutations in finalized blocks.
+async fn finalize_registration(
+    db: &essential_node::db::ConnectionPool,
+    registry: &PredicateAddress,
+    mutations: Vec<Mutation>,
+) {
+    let block = Block {
+        header: BlockHeader {
+            number: 0,
+            timestamp: Duration::from_secs(0),
+        },
+        solution_sets: vec![SolutionSet {
+            solutions: vec![Solution {
+                predicate_to_solve: registry.clone(),
+                predicate_data: vec![],
+                state_mutations: mutations,
+            }],
+        }],
+    };
+    let block_ca = db.insert_block(Arc::new(block)).await.unwrap();
+    db.finalize_block(block_ca).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_is_contract_registered() {
+    let (addr, db) = setup_node_as_server_with_db().await.unwrap();
+    let big_bang = BigBang::default();
+    let registry = big_bang.contract_registry;
+    let contract = Contract {
+        predicates: vec![],
+        salt: [1u8; 32],
+    };
+    let contract_ca = essential_hash::content_addr(&contract);
+    let client = EssentialNodeClient::new(addr).unwrap();
+
+    assert!(!client
+        .is_contract_registered(&registry, &contract_ca)
+        .await
+        .unwrap());
+
+    let mutations = register_contract_mutations(&contract).unwrap();
+    finalize_registration(&db, &registry, mutations).await;
+
+    assert!(client
+        .is_contract_registered(&registry, &contract_ca)
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn test_is_program_registered() {
+    let (addr, db) = setup_node_as_server_with_db().await.unwrap();
+    let registry = BigBang::default().program_registry;
+    let program = Program(vec![1, 2, 3]);
+    let program_ca = essential_hash::content_addr(&program);
+    let client = EssentialNodeClient::new(addr).unwrap();
+
+    assert!(!client
+        .is_program_registered(&registry, &program_ca)
+        .await
+        .unwrap());
+
+    let mutations = register_program_mutations(&program);
+    finalize_registration(&db, &registry, mutations).await;
+
+    assert!(client
+        .is_program_registered(&registry, &program_ca)
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn test_get_contract_and_program() {
+    let (addr, db) = setup_node_as_server_with_db().await.unwrap();
+    let big_bang = BigBang::default();
+    let program = Program(vec![1, 2, 3]);
+    let program_ca = essential_hash::content_addr(&program);
+    let predicate = |len| Predicate {
+        nodes: vec![
+            Node {
+                edge_start: Edge::MAX,
+                program_address: program_ca.clone(),
+                reads: Reads::Pre,
+            };
+            len
+        ],
+        edges: vec![],
+    };
+    // The contract's address does not depend on the order of its predicates, so register
+    // them out of order by address to check that order is kept.
+    let mut predicates: Vec<_> = (1..=4).map(predicate).collect();
+    predicates.sort_by_key(|p| std::cmp::Reverse(essential_hash::content_addr(p)));
+    let contract = Contract {
+        predicates,
+        salt: [1u8; 32],
+    };
+    let contract_ca = essential_hash::content_addr(&contract);
+    let client = EssentialNodeClient::new(addr).unwrap();
+
+    let r = client
+        .get_contract(&big_bang.contract_registry, &contract_ca)
+        .await
+        .unwrap();
+    assert_eq!(r, None);
+
+    // The node serves state, and the predicates are found, from finalized blocks.
+    let solution_set = register_contract_solution_set(
+        &big_bang.contract_registry,
+        &big_bang.program_registry,
+        &contract,
+        std::slice::from_ref(&program),
+    )
+    .unwrap();
+    let block = Block {
+        header: BlockHeader {
+            number: 0,
+            timestamp: Duration::from_secs(0),
+        },
+        solution_sets: vec![solution_set],
+    };
+    let block_ca = db.insert_block(Arc::new(block)).await.unwrap();
+    db.finalize_block(block_ca).await.unwrap();
+
+    let r = client
+        .get_contract(&big_bang.contract_registry, &contract_ca)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(essential_hash::content_addr(&r), contract_ca);
+    assert_eq!(r, contract);
+    let r = client
+        .get_program(&big_bang.program_registry, &program_ca)
+        .await
+        .unwrap();
+    assert_eq!(r, Some(program));
+}
+
+#[cfg(feature = "dry-run")]
+#[tokio::test]
+async fn test_dry_run() {
+    use essential_rest_client::dry_run::{DryRunOutcome, NodeSnapshot};
+
+    let (addr, db) = setup_node_as_server_with_db().await.unwrap();
+    let big_bang = BigBang::default();
+    essential_node::ensure_big_bang_block(&db, &big_bang)
+        .await
+        .unwrap();
+    let client = EssentialNodeClient::new(addr).unwrap();
+    let mut snapshot = NodeSnapshot::new().unwrap();
+    snapshot.sync(&client).await.unwrap();
+
+    let contract = Contract {
+        predicates: vec![],
+        salt: [1u8; 32],
+    };
+    let solution_set = register_contract_solution_set(
+        &big_bang.contract_registry,
+        &big_bang.program_registry,
+        &contract,
+        &[],
+    )
+    .unwrap();
+    let outcome = snapshot.dry_run(&big_bang, solution_set).await.unwrap();
+    assert!(matches!(outcome, DryRunOutcome::Valid { .. }));
+
+    let unregistered = SolutionSet {
+        solutions: vec![Solution {
+            predicate_to_solve: PredicateAddress {
+                contract: ContentAddress([42u8; 32]),
+                predicate: ContentAddress([0u8; 32]),
+            },
+            predicate_data: vec![],
+            state_mutations: vec![],
+        }],
+    };
+    let outcome = snapshot.dry_run(&big_bang, unregistered).await.unwrap();
+    assert!(matches!(outcome, DryRunOutcome::Invalid { .. }));
+}
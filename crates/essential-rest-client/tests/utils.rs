@@ -1,3 +1,5 @@
+use essential_node_types::block_notify::{BlockRx, BlockTx};
+
 const LOCALHOST: &str = "127.0.0.1";
 
 async fn test_listener() -> tokio::net::TcpListener {
@@ -7,16 +9,44 @@ async fn test_listener() -> tokio::net::TcpListener {
 }
 
 pub async fn setup_node_as_server() -> anyhow::Result<String> {
+    let (address, _db) = setup_node_as_server_with_db().await?;
+    Ok(address)
+}
+
+pub async fn setup_node_as_server_with_db(
+) -> anyhow::Result<(String, essential_node::db::ConnectionPool)> {
+    setup_node(None).await
+}
+
+/// Set up a node that streams new blocks to subscribers when notified with the returned sender.
+pub async fn setup_node_as_server_with_block_tx(
+) -> anyhow::Result<(String, essential_node::db::ConnectionPool, BlockTx)> {
+    let block_tx = BlockTx::new();
+    let (address, db) = setup_node(Some(block_tx.new_listener())).await?;
+    Ok((address, db, block_tx))
+}
+
+async fn setup_node(
+    new_block: Option<BlockRx>,
+) -> anyhow::Result<(String, essential_node::db::ConnectionPool)> {
     let conf = essential_node::db::pool::Config {
         source: essential_node::db::pool::Source::Memory(uuid::Uuid::new_v4().into()),
         ..Default::default()
     };
     let db = essential_node::db::ConnectionPool::with_tables(&conf).unwrap();
     let state = essential_node_api::State {
-        conn_pool: db,
-        new_block: None,
+        conn_pool: db.clone(),
+        new_block,
     };
     let router = essential_node_api::router(state);
+    let address = serve_router(router).await;
+    Ok((address, db))
+}
+
+/// Serve the given router on a local port, returning its address.
+///
+/// Used to stand in for a node or builder with hand-written endpoints.
+pub async fn serve_router(router: axum::Router) -> String {
     let listener = test_listener().await;
     let port = listener.local_addr().unwrap().port();
     let _jh = tokio::spawn(async move {
@@ -27,6 +57,5 @@ pub async fn setup_node_as_server() -> anyhow::Result<String> {
         )
         .await
     });
-    let address = format!("http://{LOCALHOST}:{port}/");
-    Ok(address)
+    format!("http://{LOCALHOST}:{port}/")
 }
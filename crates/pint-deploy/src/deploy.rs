@@ -0,0 +1,551 @@
+//! Deploying each contract package in turn, registering each program only once.
+
+use crate::{package::Package, record::record};
+use anyhow::bail;
+use essential_node_types::BigBang;
+use essential_rest_client::{
+    builder_client::{register_contract_solution_set, EssentialBuilderClient, SolutionSetOutcome},
+    deployments::Deployment,
+    dry_run::{DryRunOutcome, NodeSnapshot},
+    exit::{TimeoutError, ValidationError},
+    node_client::EssentialNodeClient,
+};
+use essential_types::{contract::Contract, ContentAddress, Program, Word};
+use std::{collections::BTreeMap, time::Duration};
+
+/// Deploys contracts to a builder, as configured by the command line.
+pub(crate) struct Deployer<'a> {
+    pub builder: &'a EssentialBuilderClient,
+    /// The configuration of the chain being deployed to.
+    pub big_bang: &'a BigBang,
+    /// The node to check for contracts that are already registered, if any.
+    pub registry_node: Option<&'a EssentialNodeClient>,
+    /// The node to await and verify registrations with, if any.
+    pub wait_node: Option<&'a EssentialNodeClient>,
+    /// The snapshot to validate registrations against instead of submitting them, if any.
+    pub snapshot: Option<&'a NodeSnapshot>,
+    /// How long to wait for each registration to be included in a block.
+    pub timeout: Duration,
+}
+
+/// The outcome of deploying all packages.
+#[derive(Default)]
+pub(crate) struct Outcome {
+    /// The outcome of each package, in the order they were deployed.
+    pub deployed: Vec<Deployed>,
+    /// The records of the contracts that are registered or were submitted, by name.
+    pub records: BTreeMap<String, Deployment>,
+    /// The error of each package that failed to deploy.
+    pub errors: Vec<anyhow::Error>,
+}
+
+/// The outcome of deploying a single contract.
+pub(crate) struct Deployed {
+    /// The name of the package.
+    pub name: String,
+    /// The content address of the contract.
+    pub contract: ContentAddress,
+    pub status: Status,
+    /// The content address and status of each of the contract's programs.
+    pub programs: Vec<(ContentAddress, Status)>,
+}
+
+/// Whether a contract or program's registration was submitted.
+#[derive(Clone, Debug)]
+pub(crate) enum Status {
+    /// The registration was submitted in the solution set with the given address.
+    Submitted(ContentAddress),
+    /// The registration was included in a block by the solution set with the given address.
+    Included(ContentAddress, Word),
+    /// The registration was validated in a dry run, consuming the given gas.
+    Validated(u64),
+    /// The contract or program was already deployed, so no registration was submitted.
+    Skipped,
+    /// Checking or submitting the registration failed with the given error.
+    Failed(String),
+}
+
+/// The contract and programs expected to be registered once a registration is included.
+struct Registered<'a> {
+    big_bang: &'a BigBang,
+    contract: &'a ContentAddress,
+    programs: &'a [ContentAddress],
+}
+
+impl Deployer<'_> {
+    /// Deploy the given packages in order.
+    ///
+    /// A failure to deploy one package is recorded in its outcome, and the remaining
+    /// packages are still deployed.
+    pub(crate) async fn deploy(&self, pkgs: Vec<Package>) -> Outcome {
+        let mut deployments = Outcome::default();
+        for pkg in pkgs {
+            self.deploy_package(pkg, &mut deployments).await;
+        }
+        deployments
+    }
+
+    /// Deploy a single package, adding its outcome to the given deployments.
+    async fn deploy_package(&self, pkg: Package, deployments: &mut Outcome) {
+        let Package {
+            name,
+            contract,
+            programs,
+            predicates,
+        } = pkg;
+        let contract_ca = essential_hash::content_addr(&contract);
+        let program_cas: Vec<_> = programs.iter().map(essential_hash::content_addr).collect();
+        let deployed = &deployments.deployed;
+
+        let registered = match deployed.iter().any(|d| d.has_contract(&contract_ca)) {
+            true => Ok(true),
+            false => self.is_registered(&contract_ca).await,
+        };
+        match registered {
+            Ok(false) => (),
+            Ok(true) => {
+                crate::print::skipping(&name, &contract_ca);
+                deployments.records.insert(
+                    name.clone(),
+                    record(&contract_ca, predicates, &program_cas, None),
+                );
+                // The programs were registered along with the contract.
+                deployments.deployed.push(Deployed::new(
+                    name,
+                    contract_ca,
+                    Status::Skipped,
+                    program_cas.clone(),
+                    &program_cas,
+                ));
+                return;
+            }
+            Err(err) => {
+                let status = Status::Failed(format!("{err:#}"));
+                crate::print::check_failed(&name, &contract_ca, &status);
+                let context = format!("Failed to check whether {name} is registered");
+                deployments.errors.push(err.context(context));
+                deployments.deployed.push(Deployed::new(
+                    name,
+                    contract_ca,
+                    status,
+                    program_cas.clone(),
+                    &program_cas,
+                ));
+                return;
+            }
+        }
+
+        // Programs registered along with a previous contract are not registered again.
+        let is_new = |ca: &ContentAddress| !deployed.iter().any(|d| d.has_program(ca));
+        let new_programs: Vec<_> = programs
+            .into_iter()
+            .zip(&program_cas)
+            .filter(|(_, ca)| is_new(ca))
+            .map(|(program, _)| program)
+            .collect();
+        let new_program_cas: Vec<_> = program_cas
+            .iter()
+            .filter(|ca| is_new(ca))
+            .cloned()
+            .collect();
+
+        if let Some(snapshot) = self.snapshot {
+            let status = match self.validate(snapshot, &contract, &new_programs).await {
+                Ok(total_gas) => Status::Validated(total_gas),
+                Err(err) => {
+                    let status = Status::Failed(format!("{err:#}"));
+                    deployments
+                        .errors
+                        .push(err.context(format!("Failed to validate {name}")));
+                    status
+                }
+            };
+            crate::print::validated(&name, &contract_ca, &status);
+            deployments.deployed.push(Deployed::new(
+                name,
+                contract_ca,
+                status,
+                program_cas,
+                &new_program_cas,
+            ));
+            return;
+        }
+
+        crate::print::deploying(&name, &contract_ca);
+        let status = self
+            .register(&contract, &contract_ca, &new_programs, &new_program_cas)
+            .await
+            .unwrap_or_else(|err| {
+                let status = Status::Failed(format!("{err:#}"));
+                deployments
+                    .errors
+                    .push(err.context(format!("Failed to deploy {name}")));
+                status
+            });
+        if let Status::Submitted(ca) | Status::Included(ca, _) = &status {
+            let solution_set = Some(ca.clone());
+            deployments.records.insert(
+                name.clone(),
+                record(&contract_ca, predicates, &program_cas, solution_set),
+            );
+        }
+        deployments.deployed.push(Deployed::new(
+            name,
+            contract_ca,
+            status,
+            program_cas,
+            &new_program_cas,
+        ));
+    }
+
+    /// Submit the registration of the given contract and programs, awaiting its inclusion
+    /// if there is a node to wait on.
+    async fn register(
+        &self,
+        contract: &Contract,
+        contract_ca: &ContentAddress,
+        programs: &[Program],
+        program_cas: &[ContentAddress],
+    ) -> anyhow::Result<Status> {
+        let output = self
+            .builder
+            .register_contract(
+                &self.big_bang.contract_registry,
+                &self.big_bang.program_registry,
+                contract,
+                programs,
+            )
+            .await?;
+        crate::print::received(&output);
+        let Some(node) = self.wait_node else {
+            return Ok(Status::Submitted(output));
+        };
+        let registered = Registered {
+            big_bang: self.big_bang,
+            contract: contract_ca,
+            programs: program_cas,
+        };
+        let block_number = confirm(self.builder, node, &output, registered, self.timeout).await?;
+        crate::print::included(&output, block_number);
+        Ok(Status::Included(output, block_number))
+    }
+
+    /// Validate the registration of the given contract and programs against the snapshot.
+    ///
+    /// Returns the gas the registration would consume.
+    async fn validate(
+        &self,
+        snapshot: &NodeSnapshot,
+        contract: &Contract,
+        programs: &[Program],
+    ) -> anyhow::Result<u64> {
+        let solution_set = register_contract_solution_set(
+            &self.big_bang.contract_registry,
+            &self.big_bang.program_registry,
+            contract,
+            programs,
+        )?;
+        match snapshot.dry_run(self.big_bang, solution_set).await? {
+            DryRunOutcome::Valid { total_gas } => Ok(total_gas),
+            outcome @ DryRunOutcome::Invalid { .. } => {
+                Err(ValidationError(format!("Registration {outcome}")).into())
+            }
+        }
+    }
+
+    /// Whether the given contract is already registered according to the node, if there is one.
+    async fn is_registered(&self, contract_ca: &ContentAddress) -> anyhow::Result<bool> {
+        match self.registry_node {
+            Some(node) => Ok(node
+                .is_contract_registered(&self.big_bang.contract_registry, contract_ca)
+                .await?),
+            None => Ok(false),
+        }
+    }
+}
+
+impl Deployed {
+    /// The outcome of registering a contract along with the new programs among its programs.
+    ///
+    /// New programs share the contract's status, the rest are skipped.
+    fn new(
+        name: String,
+        contract: ContentAddress,
+        status: Status,
+        programs: Vec<ContentAddress>,
+        new_programs: &[ContentAddress],
+    ) -> Self {
+        let programs = programs
+            .into_iter()
+            .map(|ca| {
+                let status = if new_programs.contains(&ca) {
+                    status.clone()
+                } else {
+                    Status::Skipped
+                };
+                (ca, status)
+            })
+            .collect();
+        Self {
+            name,
+            contract,
+            status,
+            programs,
+        }
+    }
+
+    /// Whether this is the given contract, and it is registered or was submitted.
+    fn has_contract(&self, contract_ca: &ContentAddress) -> bool {
+        self.contract == *contract_ca && self.status.is_registered()
+    }
+
+    /// Whether the given program is registered or was submitted along with this contract.
+    fn has_program(&self, program_ca: &ContentAddress) -> bool {
+        self.programs
+            .iter()
+            .any(|(ca, status)| ca == program_ca && status.is_registered())
+    }
+}
+
+impl Status {
+    /// Whether the registration was submitted, or was not required.
+    fn is_registered(&self) -> bool {
+        !matches!(self, Self::Failed(_))
+    }
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Submitted(ca) => write!(f, "submitted in solution set {ca}"),
+            Self::Included(ca, block_number) => {
+                write!(f, "included in block {block_number} by solution set {ca}")
+            }
+            Self::Validated(total_gas) => write!(f, "would succeed using {total_gas} gas"),
+            Self::Skipped => write!(f, "already deployed"),
+            Self::Failed(err) => write!(f, "failed: {err}"),
+        }
+    }
+}
+
+/// Wait for the registration solution set to be included in a block, then verify that the
+/// contract and programs are present in the registries.
+///
+/// Returns the number of the block that includes the solution set.
+async fn confirm(
+    builder_client: &EssentialBuilderClient,
+    node_client: &EssentialNodeClient,
+    solution_set_ca: &ContentAddress,
+    registered: Registered<'_>,
+    timeout: Duration,
+) -> anyhow::Result<Word> {
+    let block_number = match builder_client
+        .await_outcome(node_client, solution_set_ca, timeout)
+        .await?
+    {
+        SolutionSetOutcome::Included { block_number, .. } => block_number,
+        SolutionSetOutcome::Failed(failure) => {
+            let msg = format!("Registration failed: {}", failure.err_msg);
+            return Err(ValidationError(msg).into());
+        }
+        SolutionSetOutcome::TimedOut => {
+            let msg = format!("Registration was not included in a block within {timeout:?}");
+            return Err(TimeoutError(msg).into());
+        }
+    };
+    let Registered {
+        big_bang,
+        contract,
+        programs,
+    } = registered;
+    if !node_client
+        .is_contract_registered(&big_bang.contract_registry, contract)
+        .await?
+    {
+        bail!("Contract {contract} is not in the contract registry after block {block_number}");
+    }
+    for program in programs {
+        if !node_client
+            .is_program_registered(&big_bang.program_registry, program)
+            .await?
+        {
+            bail!("Program {program} is not in the program registry after block {block_number}");
+        }
+    }
+    Ok(block_number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::get, routing::post, Json, Router};
+    use essential_devnet::{Config, Devnet};
+    use essential_rest_client::config::ClientConfig;
+    use essential_types::solution::SolutionSet;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    fn package(name: &str, salt: u8, programs: &[&Program]) -> Package {
+        Package {
+            name: name.to_string(),
+            contract: Contract {
+                predicates: vec![],
+                salt: [salt; 32],
+            },
+            programs: programs.iter().map(|&p| p.clone()).collect(),
+            predicates: BTreeMap::new(),
+        }
+    }
+
+    fn deployer<'a>(
+        builder: &'a EssentialBuilderClient,
+        big_bang: &'a BigBang,
+        node: Option<&'a EssentialNodeClient>,
+    ) -> Deployer<'a> {
+        Deployer {
+            builder,
+            big_bang,
+            registry_node: node,
+            wait_node: node,
+            snapshot: None,
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Serve the given router on a local port, returning its address.
+    async fn serve(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let limit = essential_node_api::DEFAULT_CONNECTION_LIMIT;
+            essential_node_api::serve(&router, &listener, limit).await
+        });
+        address
+    }
+
+    /// A builder that rejects the given number of submissions, then accepts the rest.
+    async fn flaky_builder(rejections: usize) -> (String, Arc<AtomicUsize>) {
+        let submissions = Arc::new(AtomicUsize::new(0));
+        let count = submissions.clone();
+        let router = Router::new().route(
+            "/submit-solution-set",
+            post(move |Json(set): Json<SolutionSet>| async move {
+                match count.fetch_add(1, Ordering::SeqCst) < rejections {
+                    true => Err(StatusCode::BAD_REQUEST),
+                    false => Ok(Json(essential_hash::content_addr(&set))),
+                }
+            }),
+        );
+        (serve(router).await, submissions)
+    }
+
+    fn programs(deployed: &Deployed) -> Vec<(&ContentAddress, &Status)> {
+        deployed.programs.iter().map(|(ca, s)| (ca, s)).collect()
+    }
+
+    #[tokio::test]
+    async fn skips_registered_contracts_and_their_programs() {
+        let devnet = Devnet::start(Config::default()).await.unwrap();
+        let big_bang = Config::default().big_bang;
+        let config = ClientConfig::builder()
+            .poll_interval(Duration::from_millis(20))
+            .build();
+        let node =
+            EssentialNodeClient::with_config(devnet.node_address().to_string(), config).unwrap();
+        let builder = EssentialBuilderClient::new(devnet.builder_address().to_string()).unwrap();
+        let shared = Program(vec![1]);
+        let own = Program(vec![2]);
+        let registered = package("registered", 1, &[&shared]);
+        let new = package("new", 2, &[&shared, &own]);
+
+        // Register the first contract, along with the shared program, ahead of time.
+        let outcome = deployer(&builder, &big_bang, Some(&node))
+            .deploy(vec![package("registered", 1, &[&shared])])
+            .await;
+        assert!(outcome.errors.is_empty());
+        assert!(matches!(outcome.deployed[0].status, Status::Included(..)));
+
+        let Outcome {
+            deployed,
+            records,
+            errors,
+        } = deployer(&builder, &big_bang, Some(&node))
+            .deploy(vec![registered, new])
+            .await;
+        assert!(errors.is_empty(), "{errors:?}");
+        assert!(matches!(deployed[0].status, Status::Skipped));
+        assert!(matches!(programs(&deployed[0])[..], [(_, Status::Skipped)]));
+        assert!(matches!(deployed[1].status, Status::Included(..)));
+        let shared_ca = essential_hash::content_addr(&shared);
+        let own_ca = essential_hash::content_addr(&own);
+        assert!(matches!(
+            programs(&deployed[1])[..],
+            [(a, Status::Skipped), (b, Status::Included(..))] if *a == shared_ca && *b == own_ca
+        ));
+        assert!(records["registered"].solution_set.is_none());
+        assert!(records["new"].solution_set.is_some());
+    }
+
+    #[tokio::test]
+    async fn failed_deployments_do_not_stop_the_rest() {
+        let (address, submissions) = flaky_builder(1).await;
+        let builder = EssentialBuilderClient::new(address).unwrap();
+        let big_bang = BigBang::default();
+        let shared = Program(vec![1]);
+        let pkgs = vec![
+            package("rejected", 1, &[&shared]),
+            package("accepted", 2, &[&shared]),
+        ];
+
+        let Outcome {
+            deployed,
+            records,
+            errors,
+        } = deployer(&builder, &big_bang, None).deploy(pkgs).await;
+        assert_eq!(submissions.load(Ordering::SeqCst), 2);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(deployed[0].status, Status::Failed(_)));
+        assert!(matches!(
+            programs(&deployed[0])[..],
+            [(_, Status::Failed(_))]
+        ));
+        // The shared program failed to register with the first contract, so it is
+        // registered with the second.
+        assert!(matches!(deployed[1].status, Status::Submitted(_)));
+        assert!(matches!(
+            programs(&deployed[1])[..],
+            [(_, Status::Submitted(_))]
+        ));
+        assert_eq!(records.keys().collect::<Vec<_>>(), ["accepted"]);
+    }
+
+    #[tokio::test]
+    async fn registry_errors_fail_each_contract() {
+        let (address, submissions) = flaky_builder(0).await;
+        let builder = EssentialBuilderClient::new(address).unwrap();
+        let router = Router::new().route(
+            "/query-state/:contract/:key",
+            get(|| async { StatusCode::BAD_REQUEST }),
+        );
+        let node = EssentialNodeClient::new(serve(router).await).unwrap();
+        let big_bang = BigBang::default();
+        let pkgs = vec![package("a", 1, &[]), package("b", 2, &[])];
+
+        let Outcome {
+            deployed,
+            records,
+            errors,
+        } = deployer(&builder, &big_bang, Some(&node))
+            .deploy(pkgs)
+            .await;
+        assert_eq!(submissions.load(Ordering::SeqCst), 0);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(deployed.len(), 2);
+        assert!(deployed
+            .iter()
+            .all(|d| matches!(d.status, Status::Failed(_))));
+        assert!(records.is_empty());
+    }
+}
@@ -1,11 +1,19 @@
-use anyhow::{anyhow, bail};
-use clap::{builder::styling::Style, Parser};
-use essential_node_types::BigBang;
-use essential_rest_client::{builder_client::EssentialBuilderClient, contract_from_path};
-use essential_types::{contract::Contract, ContentAddress};
-use futures::future::join_all;
-use pint_pkg::build::BuiltPkg;
-use std::path::PathBuf;
+use clap::Parser;
+use deploy::{Deployer, Outcome};
+use essential_rest_client::{
+    big_bang_or_default,
+    builder_client::{print_progress, EssentialBuilderClient},
+    deployments::{Deployments, Network},
+    dry_run::NodeSnapshot,
+    exit,
+    node_client::EssentialNodeClient,
+};
+use std::{path::PathBuf, process::ExitCode, time::Duration};
+
+mod deploy;
+mod package;
+mod print;
+mod record;
 
 #[derive(Parser, Debug)]
 #[command(name = "deploy", version, about, long_about = None)]
@@ -24,15 +32,59 @@ struct Args {
     /// pint project are ignored.
     #[arg(long)]
     contract: Option<PathBuf>,
+    /// The name under which a specific `--contract` is recorded in the deployments file.
+    ///
+    /// Defaults to the name of the contract file without its extension, which is the name
+    /// of the package that built it.
+    #[arg(long, requires = "contract")]
+    name: Option<String>,
+    /// Path to the big bang configuration of the chain as YAML.
+    ///
+    /// If not specified, the default big bang configuration is used.
+    #[arg(long)]
+    big_bang: Option<PathBuf>,
+    /// The build profile whose output directory contains the built contract.
+    #[arg(long, default_value = "debug")]
+    profile: String,
+    /// The node used to check whether contracts are already registered and to await
+    /// registrations with `--wait`.
+    ///
+    /// If specified, contracts that are already registered are not deployed again.
+    #[arg(long)]
+    node_address: Option<String>,
+    /// Deploy contracts even if the node reports they are already registered.
+    #[arg(long, requires = "node_address")]
+    force: bool,
+    /// Wait for each registration to be included in a block, then verify the contract and
+    /// its programs can be queried from the registries.
+    #[arg(long, requires = "node_address")]
+    wait: bool,
+    /// Validate each registration against a local snapshot of the node instead of
+    /// submitting it.
+    ///
+    /// The snapshot copies every block of the node into memory.
+    #[arg(long, requires = "node_address", conflicts_with = "wait")]
+    dry_run: bool,
+    /// How long to wait for each registration to be included in a block, in seconds.
+    #[arg(long, default_value_t = 60)]
+    timeout: u64,
+    /// The name of the network being deployed to, as recorded in the deployments file.
+    #[arg(long, default_value = "default")]
+    network: String,
+    /// Path to the file in which deployed contracts are recorded.
+    ///
+    /// Defaults to `deployments.toml` next to the package manifest, or in the current
+    /// directory when deploying a specific `--contract`.
+    #[arg(long)]
+    deployments: Option<PathBuf>,
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let args = Args::parse();
-    if let Err(err) = run(args).await {
-        let bold = Style::new().bold();
-        eprintln!("{}Error:{} {err:?}", bold.render(), bold.render_reset());
-        std::process::exit(1);
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => exit::report(&err),
     }
 }
 
@@ -41,110 +93,84 @@ async fn run(args: Args) -> anyhow::Result<()> {
         build_args,
         builder_address,
         contract,
+        name,
+        big_bang,
+        profile,
+        node_address,
+        force,
+        wait,
+        dry_run,
+        timeout,
+        network: network_name,
+        deployments,
     } = args;
 
-    // The expected configuration of the chain we're querying.
-    // FIXME: Provide CLI arg for specifying a path to a yml like the node and builder.
-    let big_bang = BigBang::default();
+    // The expected configuration of the chain we're deploying to.
+    let big_bang = big_bang_or_default(big_bang.as_deref()).await?;
 
-    let builder_client = EssentialBuilderClient::new(builder_address)?;
+    let builder_client =
+        EssentialBuilderClient::new(builder_address.clone())?.with_progress(print_progress);
+    let node_client = node_address
+        .clone()
+        .map(EssentialNodeClient::new)
+        .transpose()?;
+    // Registration is only checked when a node is given and the deployment isn't forced.
+    let registry_node = node_client.as_ref().filter(|_| !force);
+    // Registrations are only awaited when requested.
+    let wait_node = node_client.as_ref().filter(|_| wait);
+    let timeout = Duration::from_secs(timeout);
+    // A single snapshot of the node is used to validate every registration in a dry run.
+    let snapshot = match node_client.as_ref().filter(|_| dry_run) {
+        Some(node) => {
+            let mut snapshot = NodeSnapshot::new()?;
+            snapshot.sync(node).await?;
+            Some(snapshot)
+        }
+        None => None,
+    };
 
     // If a contract was specified directly, there's no need to do the build or inspect any of the
     // `build_args` - we can deploy this directly.
-    if let Some(contract_path) = contract {
-        let (contract, programs) = contract_from_path(&contract_path).await?;
-        let name = format!(
-            "{}",
-            contract_path
-                .canonicalize()
-                .unwrap_or_else(|_| contract_path.to_path_buf())
-                .display()
-        );
-        print_deploying(&name, &contract);
-        let output = builder_client
-            .register_contract(
-                &big_bang.contract_registry,
-                &big_bang.program_registry,
-                &contract,
-                &programs,
-            )
-            .await?;
-
-        join_all(programs.iter().map(|p| {
-            let builder_client = &builder_client;
-            async {
-                builder_client
-                    .register_program(&big_bang.program_registry, p)
-                    .await
-            }
-        }))
-        .await;
-
-        print_received(&output);
-        return Ok(());
-    }
+    let (pkgs, pkg_dir) = match contract {
+        Some(contract_path) => {
+            let pkg = package::from_contract_path(&contract_path, name).await?;
+            (vec![pkg], std::env::current_dir()?)
+        }
+        // Otherwise, we should find and build the project.
+        None => package::built_contracts(build_args, &profile).await?,
+    };
+    let deployments_path = deployments.unwrap_or_else(|| pkg_dir.join(Deployments::FILE_NAME));
 
-    // Otherwise, we should find and build the project.
-    let (plan, built_pkgs) = pint_cli::build::cmd(build_args)?;
+    // Deploy each contract in order, registering each program only once.
+    let deployer = Deployer {
+        builder: &builder_client,
+        big_bang: &big_bang,
+        registry_node,
+        wait_node,
+        snapshot: snapshot.as_ref(),
+        timeout,
+    };
+    let Outcome {
+        deployed,
+        records,
+        errors,
+    } = deployer.deploy(pkgs).await;
 
-    // FIXME: We assume the package containing the output artifact is the last one in the
-    // compilation order. When supporting workspaces, we should deploy all (non-deployed) member
-    // "output" nodes in order of dependency.
-    let &n = plan
-        .compilation_order()
-        .last()
-        .ok_or_else(|| anyhow!("No built packages to deploy"))?;
-    let built = &built_pkgs[&n];
-    let pinned = &plan.graph()[n];
-    let manifest = &plan.manifests()[&pinned.id()];
+    print::summary(&deployed);
 
-    // Now that the project is built, find the contract output.
-    // FIXME: Right now assume the `debug` profile just like the `build` command does.
-    let out_dir = manifest.out_dir();
-    let profile = "debug";
-    let profile_dir = out_dir.join(profile);
-    match built {
-        BuiltPkg::Library(_) => {
-            bail!("Expected a contract to deploy, but the pint package is a library")
-        }
-        BuiltPkg::Contract(_built) => {
-            let contract_path = profile_dir.join(&pinned.name).with_extension("json");
-            let (contract, programs) = contract_from_path(&contract_path).await?;
-            print_deploying(&pinned.name, &contract);
-            let output = builder_client
-                .register_contract(
-                    &big_bang.contract_registry,
-                    &big_bang.program_registry,
-                    &contract,
-                    &programs,
-                )
-                .await?;
-            print_received(&output);
-        }
+    let network = Network {
+        builder_address,
+        node_address,
+        contracts: records,
+    };
+    if record::record_deployments(&deployments_path, network_name, network, dry_run).await? {
+        print::recorded(&deployments_path);
     }
 
-    Ok(())
-}
-
-/// Print the "Deploying ..." output with nice, aligned formatting.
-fn print_deploying(name: &str, contract: &Contract) {
-    let bold = Style::new().bold();
-    println!(
-        "   {}Deploying{} {} {}",
-        bold.render(),
-        bold.render_reset(),
-        name,
-        essential_hash::content_addr(contract),
-    );
-}
-
-/// Print the "Received ..." output.
-fn print_received(ca: &ContentAddress) {
-    let bold = Style::new().bold();
-    println!(
-        "    {}Received{} solution address {}",
-        bold.render(),
-        bold.render_reset(),
-        ca
-    );
+    // Report the first failure, after all deployments have been attempted.
+    let failed = errors.len();
+    match errors.into_iter().next() {
+        Some(err) => Err(err.context(format!("{failed} of {} deployments failed", deployed.len()))),
+        None => Ok(()),
+    }
 }
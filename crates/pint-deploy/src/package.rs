@@ -0,0 +1,131 @@
+//! Finding the contracts to deploy, either built from a project or read from a file.
+
+use anyhow::bail;
+use essential_rest_client::contract_from_path;
+use essential_types::{contract::Contract, ContentAddress, Program};
+use pint_pkg::build::BuiltPkg;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// A contract to deploy.
+pub(crate) struct Package {
+    /// The name of the package, under which the contract is recorded.
+    pub name: String,
+    pub contract: Contract,
+    pub programs: Vec<Program>,
+    /// The content address of each predicate, by name.
+    pub predicates: BTreeMap<String, ContentAddress>,
+}
+
+/// Read a specific contract and its programs from the given JSON file.
+///
+/// The contract is named `name` if given, or otherwise after the file without its extension.
+/// Built contracts are written to `<package>.json`, so this is the name of the package that
+/// built it.
+pub(crate) async fn from_contract_path(
+    contract_path: &Path,
+    name: Option<String>,
+) -> anyhow::Result<Package> {
+    let (contract, programs) = contract_from_path(contract_path).await?;
+    let name = match name {
+        Some(name) => name,
+        None => match contract_path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(stem) => stem.to_string(),
+            None => bail!("Could not name the contract at {contract_path:?}, specify a `--name`"),
+        },
+    };
+    // A contract file has no predicate names, so predicates are named by position.
+    let predicates = contract
+        .predicates
+        .iter()
+        .enumerate()
+        .map(|(ix, predicate)| (ix.to_string(), essential_hash::content_addr(predicate)))
+        .collect();
+    Ok(Package {
+        name,
+        contract,
+        programs,
+        predicates,
+    })
+}
+
+/// Build the project, returning each contract package in dependency order, so that contracts
+/// are registered before those that reference them, along with the member package's directory.
+pub(crate) async fn built_contracts(
+    build_args: pint_cli::build::Args,
+    profile: &str,
+) -> anyhow::Result<(Vec<Package>, PathBuf)> {
+    let (plan, built_pkgs) = pint_cli::build::cmd(build_args)?;
+    let member = plan.compilation_order().last().copied();
+    let mut pkgs = vec![];
+    for &n in plan.compilation_order() {
+        let BuiltPkg::Contract(built) = &built_pkgs[&n] else {
+            continue;
+        };
+        let pinned = &plan.graph()[n];
+        let manifest = &plan.manifests()[&pinned.id()];
+
+        // Only the member package is written to its `out` directory by the build, so the
+        // artifacts of dependencies are taken directly from the build.
+        let (contract, programs) = if Some(n) == member {
+            let profile_dir = manifest.out_dir().join(profile);
+            let contract_path = profile_dir.join(&pinned.name).with_extension("json");
+            if !contract_path.exists() {
+                bail!(
+                    "Could not find the built contract at {:?}. Has it been built with the `{}` profile?",
+                    contract_path,
+                    profile
+                );
+            }
+            contract_from_path(&contract_path).await?
+        } else {
+            let programs = built.programs.iter().cloned().collect();
+            (built.contract.clone(), programs)
+        };
+        let predicates = built
+            .predicate_metadata
+            .iter()
+            .map(|predicate| (predicate.name.clone(), predicate.ca.clone()))
+            .collect();
+        pkgs.push(Package {
+            name: pinned.name.clone(),
+            contract,
+            programs,
+            predicates,
+        });
+    }
+    if pkgs.is_empty() {
+        bail!("Expected a contract to deploy, but no contract packages were built")
+    }
+    let member_dir = member
+        .map(|n| plan.manifests()[&plan.graph()[n].id()].dir().to_path_buf())
+        .ok_or_else(|| anyhow::anyhow!("No built packages to deploy"))?;
+    Ok((pkgs, member_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn contracts_are_named_after_their_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out/debug/counter.json");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let contract = Contract {
+            predicates: vec![],
+            salt: [0; 32],
+        };
+        let json = serde_json::to_string(&(contract, Vec::<Program>::new())).unwrap();
+        std::fs::write(&path, json).unwrap();
+
+        let pkg = from_contract_path(&path, None).await.unwrap();
+        assert_eq!(pkg.name, "counter");
+        let pkg = from_contract_path(&path, Some("renamed".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(pkg.name, "renamed");
+    }
+}
@@ -0,0 +1,106 @@
+//! Printing the progress and outcome of deployments with nice, aligned formatting.
+
+use crate::deploy::{Deployed, Status};
+use clap::builder::styling::Style;
+use essential_types::{ContentAddress, Word};
+use std::path::Path;
+
+/// Print the address and status of each deployed contract and its programs, aligned by name.
+pub(crate) fn summary(deployed: &[Deployed]) {
+    let bold = Style::new().bold();
+    println!("     {}Summary{}", bold.render(), bold.render_reset());
+    let width = deployed.iter().map(|d| d.name.len()).max().unwrap_or(0);
+    for d in deployed {
+        println!(
+            "             {:<width$} {} {}",
+            d.name, d.contract, d.status
+        );
+        for (ca, status) in &d.programs {
+            println!("             {:<width$}   program {} {}", "", ca, status);
+        }
+    }
+}
+
+/// Print the "Skipping ..." output for a contract that is already deployed.
+pub(crate) fn skipping(name: &str, contract_ca: &ContentAddress) {
+    let bold = Style::new().bold();
+    println!(
+        "    {}Skipping{} {} {} (already deployed)",
+        bold.render(),
+        bold.render_reset(),
+        name,
+        contract_ca,
+    );
+}
+
+/// Print the "Checking ..." output for a contract whose registration could not be checked.
+pub(crate) fn check_failed(name: &str, contract_ca: &ContentAddress, status: &Status) {
+    let bold = Style::new().bold();
+    println!(
+        "    {}Checking{} {} {}: {}",
+        bold.render(),
+        bold.render_reset(),
+        name,
+        contract_ca,
+        status,
+    );
+}
+
+/// Print the "Validated ..." output for a dry run.
+pub(crate) fn validated(name: &str, contract_ca: &ContentAddress, status: &Status) {
+    let bold = Style::new().bold();
+    println!(
+        "   {}Validated{} {} {}: {}",
+        bold.render(),
+        bold.render_reset(),
+        name,
+        contract_ca,
+        status,
+    );
+}
+
+/// Print the "Deploying ..." output.
+pub(crate) fn deploying(name: &str, contract_ca: &ContentAddress) {
+    let bold = Style::new().bold();
+    println!(
+        "   {}Deploying{} {} {}",
+        bold.render(),
+        bold.render_reset(),
+        name,
+        contract_ca,
+    );
+}
+
+/// Print the "Received ..." output.
+pub(crate) fn received(ca: &ContentAddress) {
+    let bold = Style::new().bold();
+    println!(
+        "    {}Received{} solution address {}",
+        bold.render(),
+        bold.render_reset(),
+        ca
+    );
+}
+
+/// Print the "Included ..." output.
+pub(crate) fn included(ca: &ContentAddress, block_number: Word) {
+    let bold = Style::new().bold();
+    println!(
+        "    {}Included{} solution address {} in block {}",
+        bold.render(),
+        bold.render_reset(),
+        ca,
+        block_number
+    );
+}
+
+/// Print the "Recorded ..." output.
+pub(crate) fn recorded(path: &Path) {
+    let bold = Style::new().bold();
+    println!(
+        "    {}Recorded{} deployments in {}",
+        bold.render(),
+        bold.render_reset(),
+        path.display()
+    );
+}
@@ -0,0 +1,103 @@
+//! Recording deployed contracts in the deployments file.
+
+use essential_rest_client::deployments::{Deployment, Deployments, Network};
+use essential_types::ContentAddress;
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The record of a deployed contract, timestamped now.
+pub(crate) fn record(
+    contract: &ContentAddress,
+    predicates: BTreeMap<String, ContentAddress>,
+    programs: &[ContentAddress],
+    solution_set: Option<ContentAddress>,
+) -> Deployment {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Deployment {
+        address: contract.clone(),
+        predicates,
+        programs: programs.to_vec(),
+        solution_set,
+        timestamp,
+    }
+}
+
+/// Merge the given network's addresses and contracts into the deployments file at `path`.
+///
+/// Nothing is written in a dry run, or if there are no contracts to record. Returns whether
+/// the file was written.
+pub(crate) async fn record_deployments(
+    path: &Path,
+    network_name: String,
+    network: Network,
+    dry_run: bool,
+) -> anyhow::Result<bool> {
+    if dry_run || network.contracts.is_empty() {
+        return Ok(false);
+    }
+    let mut file = Deployments::from_path(path).await?;
+    let entry = file.networks.entry(network_name).or_default();
+    entry.builder_address = network.builder_address;
+    entry.node_address = network.node_address;
+    for (name, record) in network.contracts {
+        // Keep the original registration of contracts that were already registered.
+        match entry.contracts.get(&name) {
+            Some(existing)
+                if record.solution_set.is_none() && existing.address == record.address => {}
+            _ => {
+                entry.contracts.insert(name, record);
+            }
+        }
+    }
+    file.write(path).await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn dry_run_leaves_deployments_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(Deployments::FILE_NAME);
+        let existing = "[networks.default]\nbuilder_address = \"http://old\"\n";
+        std::fs::write(&path, existing).unwrap();
+        let contract = ContentAddress([1; 32]);
+        let network = || Network {
+            builder_address: "http://new".to_string(),
+            node_address: Some("http://node".to_string()),
+            contracts: [(
+                "counter".to_string(),
+                record(&contract, [].into(), &[], None),
+            )]
+            .into(),
+        };
+
+        let written = record_deployments(&path, "default".to_string(), network(), true)
+            .await
+            .unwrap();
+        assert!(!written);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), existing);
+
+        let written = record_deployments(&path, "default".to_string(), network(), false)
+            .await
+            .unwrap();
+        assert!(written);
+        let deployments = Deployments::from_path(&path).await.unwrap();
+        assert_eq!(
+            deployments.networks["default"].builder_address,
+            "http://new"
+        );
+        assert_eq!(
+            deployments.contract("default", "counter").unwrap().address,
+            contract
+        );
+    }
+}
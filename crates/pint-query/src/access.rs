@@ -0,0 +1,667 @@
+//! Parsing of storage access expressions and derivation of their keys from a contract's ABI.
+//!
+//! A storage access names a storage variable followed by any number of accessors:
+//!
+//! - `[<value>]` indexes a map by key or an array by index, e.g. `balances[0x01..ff]`.
+//! - `.<field>` accesses a tuple field by name or by position, e.g. `config.owner` or `pair.0`.
+//!
+//! Map keys are written according to the map's key type:
+//!
+//! - `int`: a decimal integer or a `0x`-prefixed hex word, e.g. `42`, `-1`, `0x2a`.
+//! - `bool`: `true` or `false`.
+//! - `real`: a decimal number, e.g. `1.5`.
+//! - `b256`: a `0x`-prefixed 32 byte hex string.
+//! - tuples: `{<value>, <value>, ..}`.
+//! - arrays: `[<value>, <value>, ..]`.
+//! - unions: the variant name followed by its value in parentheses if it has one,
+//!   e.g. `Some(42)` or `None`.
+
+use essential_types::{
+    convert::{bytes_from_word, word_4_from_u8_32},
+    Key, Word,
+};
+use pint_abi::{
+    key::{Elem, Nesting},
+    types::{ContractABI, TupleField, TypeABI, UnionVariant},
+};
+
+/// A parsed storage access, e.g. `balances[0x..]` or `config.owner`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StorageAccess {
+    /// The name of the storage variable.
+    pub var: String,
+    /// The accessors applied to the storage variable, in order.
+    pub accessors: Vec<Accessor>,
+}
+
+/// A single accessor applied to a storage variable.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Accessor {
+    /// A map key or array index, as the unparsed text between the brackets.
+    Index(String),
+    /// A tuple field name or position.
+    Field(String),
+}
+
+impl std::str::FromStr for StorageAccess {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let var_end = s.find(['[', '.']).unwrap_or(s.len());
+        let var = s[..var_end].trim().to_string();
+        if var.is_empty() {
+            anyhow::bail!("Storage access `{s}` is missing a storage variable name");
+        }
+        let mut accessors = vec![];
+        let mut rest = &s[var_end..];
+        while let Some(c) = rest.chars().next() {
+            match c {
+                '[' => {
+                    let end = matching_close(rest)
+                        .ok_or_else(|| anyhow::anyhow!("Unclosed `[` in storage access `{s}`"))?;
+                    accessors.push(Accessor::Index(rest[1..end].trim().to_string()));
+                    rest = &rest[end + 1..];
+                }
+                '.' => {
+                    let end = rest[1..].find(['[', '.']).map_or(rest.len(), |ix| ix + 1);
+                    let field = rest[1..end].trim();
+                    if field.is_empty() {
+                        anyhow::bail!("Empty field name in storage access `{s}`");
+                    }
+                    accessors.push(Accessor::Field(field.to_string()));
+                    rest = &rest[end..];
+                }
+                c if c.is_whitespace() => rest = &rest[c.len_utf8()..],
+                c => anyhow::bail!("Unexpected `{c}` in storage access `{s}`"),
+            }
+        }
+        Ok(Self { var, accessors })
+    }
+}
+
+/// Derive the key for the given storage access along with the type of the accessed value.
+///
+/// Tuples and arrays are stored across multiple keys, see [`leaves`].
+pub fn key<'a>(abi: &'a ContractABI, access: &StorageAccess) -> anyhow::Result<(Key, &'a TypeABI)> {
+    let (nesting, elems, ty) = resolve(abi, access)?;
+    match ty {
+        TypeABI::Map { .. } => {
+            anyhow::bail!("`{}` is a map. Provide a key, e.g. `[<key>]`", access.var)
+        }
+        TypeABI::Tuple(_) | TypeABI::Array { .. } => anyhow::bail!(
+            "`{}` is a {} stored across multiple keys. Access one of its elements",
+            access.var,
+            ty_name(ty)
+        ),
+        _ => Ok((pint_abi::key::construct(&nesting, &elems), ty)),
+    }
+}
+
+/// Derive the key of the given storage access, whatever the type of the accessed value.
+///
+/// For maps this is the prefix shared by the keys of all of the map's entries.
+pub fn key_prefix(abi: &ContractABI, access: &StorageAccess) -> anyhow::Result<Key> {
+    let (nesting, elems, _) = resolve(abi, access)?;
+    Ok(pint_abi::key::construct(&nesting, &elems))
+}
+
+/// The type of the value accessed by the given storage access.
+pub fn accessed_ty<'a>(
+    abi: &'a ContractABI,
+    access: &StorageAccess,
+) -> anyhow::Result<&'a TypeABI> {
+    resolve(abi, access).map(|(_, _, ty)| ty)
+}
+
+/// Expand the given storage access into an access for each key-addressable leaf of the
+/// accessed value, in the order the leaves appear in the type.
+///
+/// Accesses to tuples and arrays are expanded into accesses to each of their elements.
+/// Any other access is returned as is.
+pub fn leaves(abi: &ContractABI, access: &StorageAccess) -> anyhow::Result<Vec<StorageAccess>> {
+    let ty = accessed_ty(abi, access)?;
+    let mut leaves = vec![];
+    push_leaves(ty, access.clone(), &mut leaves)?;
+    Ok(leaves)
+}
+
+/// The key of every leaf in the contract's storage, in the order of the ABI.
+///
+/// Returns `None` if the storage contains a map, as the keys of a map's entries cannot
+/// be derived from the ABI.
+pub fn storage_keys(abi: &ContractABI) -> anyhow::Result<Option<Vec<Key>>> {
+    if abi.storage.iter().any(|var| contains_map(&var.ty)) {
+        return Ok(None);
+    }
+    let mut keys = vec![];
+    for var in &abi.storage {
+        let access = StorageAccess {
+            var: var.name.clone(),
+            accessors: vec![],
+        };
+        for leaf in leaves(abi, &access)? {
+            keys.push(key(abi, &leaf)?.0);
+        }
+    }
+    Ok(Some(keys))
+}
+
+/// Whether a value of the given type is or contains a map.
+fn contains_map(ty: &TypeABI) -> bool {
+    match ty {
+        TypeABI::Map { .. } => true,
+        TypeABI::Tuple(fields) => fields.iter().any(|field| contains_map(&field.ty)),
+        TypeABI::Array { ty, .. } => contains_map(ty),
+        _ => false,
+    }
+}
+
+fn push_leaves(
+    ty: &TypeABI,
+    access: StorageAccess,
+    leaves: &mut Vec<StorageAccess>,
+) -> anyhow::Result<()> {
+    let with = |accessor| {
+        let mut access = access.clone();
+        access.accessors.push(accessor);
+        access
+    };
+    match ty {
+        TypeABI::Tuple(fields) => {
+            for (ix, field) in fields.iter().enumerate() {
+                push_leaves(&field.ty, with(Accessor::Field(ix.to_string())), leaves)?;
+            }
+        }
+        TypeABI::Array { ty, size } => {
+            for ix in 0..*size {
+                push_leaves(ty, with(Accessor::Index(ix.to_string())), leaves)?;
+            }
+        }
+        TypeABI::Map { .. } => {
+            anyhow::bail!(
+                "`{}` contains a map. Provide a key, e.g. `[<key>]`",
+                access.var
+            )
+        }
+        _ => leaves.push(access),
+    }
+    Ok(())
+}
+
+/// Resolve the nesting, key elements and type of the value accessed by the given access.
+fn resolve<'a>(
+    abi: &'a ContractABI,
+    access: &StorageAccess,
+) -> anyhow::Result<(Vec<Nesting>, Vec<Elem>, &'a TypeABI)> {
+    let (ix, var) = abi
+        .storage
+        .iter()
+        .enumerate()
+        .find(|(_, var)| names_match(&var.name, &access.var))
+        .ok_or_else(|| anyhow::anyhow!("Could not find key \"{}\" in ABI", access.var))?;
+
+    let mut nesting = vec![Nesting::Var { ix }];
+    let mut elems = vec![];
+    let mut ty = &var.ty;
+    for accessor in &access.accessors {
+        ty = match (ty, accessor) {
+            (TypeABI::Map { ty_from, ty_to }, Accessor::Index(text)) => {
+                let key = parse_value(ty_from, text)?;
+                nesting.push(Nesting::MapEntry);
+                elems.push(Elem::MapKey(key));
+                ty_to
+            }
+            (TypeABI::Array { ty: elem_ty, size }, Accessor::Index(text)) => {
+                let ix: usize = text
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid array index `{text}`"))?;
+                if ix as i64 >= *size {
+                    anyhow::bail!("Array index {ix} is out of bounds for an array of size {size}");
+                }
+                nesting.push(Nesting::ArrayElem {
+                    elem_len: flattened_key_count(elem_ty),
+                });
+                elems.push(Elem::ArrayIx(ix));
+                elem_ty
+            }
+            (TypeABI::Tuple(fields), Accessor::Field(name)) => {
+                let field_ix = find_field(fields, name)?;
+                nesting.push(Nesting::TupleField {
+                    flat_ix: fields[..field_ix]
+                        .iter()
+                        .map(|f| flattened_key_count(&f.ty))
+                        .sum(),
+                });
+                &fields[field_ix].ty
+            }
+            (ty, Accessor::Index(_)) => {
+                anyhow::bail!("Cannot index into a value of type {}", ty_name(ty))
+            }
+            (ty, Accessor::Field(_)) => {
+                anyhow::bail!("Cannot access a field of a value of type {}", ty_name(ty))
+            }
+        };
+    }
+    Ok((nesting, elems, ty))
+}
+
+/// Parse the given text as a value of the given type, returning its word encoding.
+pub fn parse_value(ty: &TypeABI, text: &str) -> anyhow::Result<Vec<Word>> {
+    let mut parser = ValueParser { text, pos: 0 };
+    let words = parser.value(ty)?;
+    parser.skip_whitespace();
+    if parser.pos != text.len() {
+        anyhow::bail!("Unexpected trailing input in `{text}`");
+    }
+    Ok(words)
+}
+
+/// Format the word encoding of a value of the given type as text accepted by [`parse_value`].
+pub fn format_value(ty: &TypeABI, words: &[Word]) -> anyhow::Result<String> {
+    if words.len() != ty_size(ty)? {
+        anyhow::bail!(
+            "Expected {} word(s) for a {} but found {}",
+            ty_size(ty)?,
+            ty_name(ty),
+            words.len()
+        );
+    }
+    Ok(match ty {
+        TypeABI::Bool => (words[0] != 0).to_string(),
+        TypeABI::Int => words[0].to_string(),
+        TypeABI::Real => f64::from_bits(words[0] as u64).to_string(),
+        TypeABI::B256 => {
+            let bytes: Vec<u8> = words.iter().copied().flat_map(bytes_from_word).collect();
+            format!("0x{}", hex::encode_upper(bytes))
+        }
+        TypeABI::Tuple(fields) => {
+            let tys: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+            format!("{{{}}}", format_sequence(&tys, words)?)
+        }
+        TypeABI::Array { ty, size } => {
+            let tys = vec![&**ty; *size as usize];
+            format!("[{}]", format_sequence(&tys, words)?)
+        }
+        TypeABI::Union { variants, .. } => {
+            let variant = usize::try_from(words[0])
+                .ok()
+                .and_then(|tag| variants.get(tag))
+                .ok_or_else(|| anyhow::anyhow!("Invalid union tag {}", words[0]))?;
+            match &variant.ty {
+                None => variant.name.clone(),
+                Some(ty) => {
+                    let payload = &words[1..1 + ty_size(ty)?];
+                    format!("{}({})", variant.name, format_value(ty, payload)?)
+                }
+            }
+        }
+        TypeABI::String | TypeABI::Map { .. } => {
+            anyhow::bail!("Values of type {} cannot be used as keys", ty_name(ty))
+        }
+    })
+}
+
+/// Format consecutive values of the given types as a comma separated list.
+fn format_sequence(tys: &[&TypeABI], mut words: &[Word]) -> anyhow::Result<String> {
+    let mut values = vec![];
+    for ty in tys {
+        let (value, rest) = words.split_at(ty_size(ty)?);
+        values.push(format_value(ty, value)?);
+        words = rest;
+    }
+    Ok(values.join(", "))
+}
+
+/// Whether a storage variable or predicate name from the ABI matches the name given by the
+/// user, ignoring any leading `::`.
+pub fn names_match(abi_name: &str, name: &str) -> bool {
+    abi_name == name || abi_name.trim_start_matches("::") == name.trim_start_matches("::")
+}
+
+/// Find the position of the tuple field with the given name or positional index.
+fn find_field(fields: &[TupleField], name: &str) -> anyhow::Result<usize> {
+    if let Ok(ix) = name.parse::<usize>() {
+        if ix < fields.len() {
+            return Ok(ix);
+        }
+        anyhow::bail!("Tuple has no field at position {ix}");
+    }
+    fields
+        .iter()
+        .position(|f| f.name.as_deref() == Some(name))
+        .ok_or_else(|| anyhow::anyhow!("Tuple has no field named `{name}`"))
+}
+
+/// The number of keys the given type occupies once directly nested tuples and arrays
+/// are flattened, matching the layout used by `pint_abi::key::construct`.
+fn flattened_key_count(ty: &TypeABI) -> usize {
+    match ty {
+        TypeABI::Tuple(fields) => fields.iter().map(|f| flattened_key_count(&f.ty)).sum(),
+        TypeABI::Array { ty, size } => *size as usize * flattened_key_count(ty),
+        _ => 1,
+    }
+}
+
+/// The number of words used to encode a value of the given type.
+pub fn ty_size(ty: &TypeABI) -> anyhow::Result<usize> {
+    Ok(match ty {
+        TypeABI::Bool | TypeABI::Int | TypeABI::Real => 1,
+        TypeABI::B256 => 4,
+        TypeABI::Tuple(fields) => fields
+            .iter()
+            .map(|f| ty_size(&f.ty))
+            .sum::<anyhow::Result<usize>>()?,
+        TypeABI::Array { ty, size } => ty_size(ty)? * *size as usize,
+        TypeABI::Union { variants, .. } => union_size(variants)?,
+        TypeABI::String | TypeABI::Map { .. } => {
+            anyhow::bail!("A {} has no fixed size", ty_name(ty))
+        }
+    })
+}
+
+/// The number of words used to encode a union with the given variants.
+///
+/// This is a tag word followed by enough words for the largest variant.
+fn union_size(variants: &[UnionVariant]) -> anyhow::Result<usize> {
+    let mut max = 0;
+    for ty in variants.iter().filter_map(|v| v.ty.as_ref()) {
+        max = max.max(ty_size(ty)?);
+    }
+    Ok(1 + max)
+}
+
+/// A short, human readable name for the kind of the given type.
+pub fn ty_name(ty: &TypeABI) -> &'static str {
+    match ty {
+        TypeABI::Bool => "bool",
+        TypeABI::Int => "int",
+        TypeABI::Real => "real",
+        TypeABI::String => "string",
+        TypeABI::B256 => "b256",
+        TypeABI::Tuple(_) => "tuple",
+        TypeABI::Array { .. } => "array",
+        TypeABI::Union { .. } => "union",
+        TypeABI::Map { .. } => "map",
+    }
+}
+
+/// Find the index of the bracket closing the one that opens the given string.
+fn matching_close(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (ix, c) in s.char_indices() {
+        match c {
+            '[' | '{' | '(' => depth += 1,
+            ']' | '}' | ')' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(ix);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// A recursive descent parser for values written in map keys.
+struct ValueParser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl ValueParser<'_> {
+    fn value(&mut self, ty: &TypeABI) -> anyhow::Result<Vec<Word>> {
+        self.skip_whitespace();
+        match ty {
+            TypeABI::Bool => match self.token() {
+                "true" => Ok(vec![1]),
+                "false" => Ok(vec![0]),
+                t => anyhow::bail!("Expected `true` or `false`, found `{t}`"),
+            },
+            TypeABI::Int => {
+                let t = self.token();
+                let word = match t.strip_prefix("0x") {
+                    Some(hex) => u64::from_str_radix(hex, 16).map(|w| w as Word),
+                    None => t.parse(),
+                };
+                Ok(vec![word.map_err(|_| anyhow::anyhow!("Invalid int `{t}`"))?])
+            }
+            TypeABI::Real => {
+                let t = self.token();
+                let real: f64 = t
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid real `{t}`"))?;
+                Ok(vec![real.to_bits() as Word])
+            }
+            TypeABI::B256 => {
+                let t = self.token();
+                let bytes: [u8; 32] = t
+                    .strip_prefix("0x")
+                    .and_then(|hex| hex::decode(hex).ok())
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Invalid b256 `{t}`, expected 0x followed by 64 hex digits")
+                    })?;
+                Ok(word_4_from_u8_32(bytes).to_vec())
+            }
+            TypeABI::Tuple(fields) => {
+                let tys: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+                self.sequence('{', '}', &tys)
+            }
+            TypeABI::Array { ty, size } => {
+                let tys = vec![&**ty; *size as usize];
+                self.sequence('[', ']', &tys)
+            }
+            TypeABI::Union { variants, .. } => {
+                let t = self.token();
+                let (tag, variant) = variants
+                    .iter()
+                    .enumerate()
+                    .find(|(_, v)| names_match(&v.name, t) || v.name.rsplit("::").next() == Some(t))
+                    .ok_or_else(|| anyhow::anyhow!("Unknown union variant `{t}`"))?;
+                let mut words = vec![tag as Word];
+                if let Some(ty) = &variant.ty {
+                    self.expect('(')?;
+                    words.extend(self.value(ty)?);
+                    self.expect(')')?;
+                }
+                words.resize(union_size(variants)?, 0);
+                Ok(words)
+            }
+            TypeABI::String | TypeABI::Map { .. } => {
+                anyhow::bail!("Values of type {} cannot be used as keys", ty_name(ty))
+            }
+        }
+    }
+
+    /// Parse a comma separated sequence of values with the given types between delimiters.
+    fn sequence(&mut self, open: char, close: char, tys: &[&TypeABI]) -> anyhow::Result<Vec<Word>> {
+        self.expect(open)?;
+        let mut words = vec![];
+        for (ix, ty) in tys.iter().enumerate() {
+            if ix > 0 {
+                self.expect(',')?;
+            }
+            words.extend(self.value(ty)?);
+        }
+        self.skip_whitespace();
+        if self.peek() == Some(',') {
+            self.pos += 1;
+        }
+        self.expect(close)?;
+        Ok(words)
+    }
+
+    /// Take the next run of characters up to a delimiter.
+    fn token(&mut self) -> &str {
+        self.skip_whitespace();
+        let start = self.pos;
+        let len = self.text[start..]
+            .find(|c: char| c.is_whitespace() || "[]{}(),".contains(c))
+            .unwrap_or(self.text.len() - start);
+        self.pos += len;
+        &self.text[start..self.pos]
+    }
+
+    fn expect(&mut self, c: char) -> anyhow::Result<()> {
+        self.skip_whitespace();
+        if self.peek() != Some(c) {
+            anyhow::bail!("Expected `{c}` at position {} of `{}`", self.pos, self.text);
+        }
+        self.pos += c.len_utf8();
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pint_abi::types::ParamABI;
+
+    fn abi() -> ContractABI {
+        let field = |name: &str, ty| TupleField {
+            name: Some(name.to_string()),
+            ty,
+        };
+        ContractABI {
+            predicates: vec![],
+            storage: vec![
+                ParamABI {
+                    name: "counter".to_string(),
+                    ty: TypeABI::Int,
+                },
+                ParamABI {
+                    name: "balances".to_string(),
+                    ty: TypeABI::Map {
+                        ty_from: Box::new(TypeABI::B256),
+                        ty_to: Box::new(TypeABI::Int),
+                    },
+                },
+                ParamABI {
+                    name: "config".to_string(),
+                    ty: TypeABI::Tuple(vec![
+                        field(
+                            "limits",
+                            TypeABI::Array {
+                                ty: Box::new(TypeABI::Int),
+                                size: 3,
+                            },
+                        ),
+                        field("owner", TypeABI::B256),
+                    ]),
+                },
+                ParamABI {
+                    name: "nested".to_string(),
+                    ty: TypeABI::Map {
+                        ty_from: Box::new(TypeABI::Int),
+                        ty_to: Box::new(TypeABI::Map {
+                            ty_from: Box::new(TypeABI::Bool),
+                            ty_to: Box::new(TypeABI::Int),
+                        }),
+                    },
+                },
+            ],
+        }
+    }
+
+    fn key_of(access: &str) -> anyhow::Result<Key> {
+        let abi = abi();
+        let access: StorageAccess = access.parse()?;
+        key(&abi, &access).map(|(key, _)| key)
+    }
+
+    #[test]
+    fn primitive_key() {
+        assert_eq!(key_of("counter").unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn map_key() {
+        let hex = format!("0x{}", "00".repeat(31) + "2a");
+        assert_eq!(
+            key_of(&format!("balances[{hex}]")).unwrap(),
+            vec![1, 0, 0, 0, 42]
+        );
+    }
+
+    #[test]
+    fn tuple_field_and_array_keys() {
+        assert_eq!(key_of("config.limits[2]").unwrap(), vec![2, 2]);
+        assert_eq!(key_of("config.owner").unwrap(), vec![2, 3]);
+        assert_eq!(key_of("config.1").unwrap(), vec![2, 3]);
+    }
+
+    #[test]
+    fn nested_map_key() {
+        assert_eq!(key_of("nested[7][true]").unwrap(), vec![3, 7, 1]);
+    }
+
+    #[test]
+    fn tuple_leaves() {
+        let abi = abi();
+        let access: StorageAccess = "config".parse().unwrap();
+        let keys: Vec<_> = leaves(&abi, &access)
+            .unwrap()
+            .iter()
+            .map(|leaf| key(&abi, leaf).unwrap().0)
+            .collect();
+        assert_eq!(keys, vec![vec![2, 0], vec![2, 1], vec![2, 2], vec![2, 3]]);
+    }
+
+    #[test]
+    fn storage_keys_without_maps() {
+        let mut abi = abi();
+        assert_eq!(storage_keys(&abi).unwrap(), None);
+        abi.storage.retain(|var| !contains_map(&var.ty));
+        assert_eq!(
+            storage_keys(&abi).unwrap(),
+            Some(vec![
+                vec![0],
+                vec![1, 0],
+                vec![1, 1],
+                vec![1, 2],
+                vec![1, 3]
+            ])
+        );
+    }
+
+    #[test]
+    fn format_parse_round_trip() {
+        let ty = TypeABI::Tuple(vec![
+            TupleField {
+                name: None,
+                ty: TypeABI::B256,
+            },
+            TupleField {
+                name: None,
+                ty: TypeABI::Array {
+                    ty: Box::new(TypeABI::Bool),
+                    size: 2,
+                },
+            },
+        ]);
+        let words = vec![0, 0, 0, 42, 1, 0];
+        let text = format_value(&ty, &words).unwrap();
+        assert_eq!(text, format!("{{0x{}2A, [true, false]}}", "0".repeat(62)));
+        assert_eq!(parse_value(&ty, &text).unwrap(), words);
+    }
+
+    #[test]
+    fn invalid_accesses() {
+        assert!(key_of("balances").is_err());
+        assert!(key_of("config").is_err());
+        assert!(key_of("config.limits[3]").is_err());
+        assert!(key_of("counter[1]").is_err());
+        assert!(key_of("missing").is_err());
+    }
+}
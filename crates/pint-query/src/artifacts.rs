@@ -0,0 +1,69 @@
+//! Locating a package's manifest and reading the artifacts of its build.
+
+use essential_rest_client::contract_from_path;
+use essential_types::{Contract, Program};
+use pint_abi::types::ContractABI;
+use pint_manifest::ManifestFile;
+use std::path::PathBuf;
+
+/// Load the manifest at the given path, or the manifest found in the current or parent
+/// directories.
+pub fn load_manifest(manifest_path: Option<PathBuf>) -> anyhow::Result<ManifestFile> {
+    let manifest_path = match manifest_path {
+        Some(path) => path,
+        None => match find_file(std::env::current_dir()?, ManifestFile::FILE_NAME) {
+            Some(path) => path,
+            None => {
+                anyhow::bail!("Pint manifest could not be found in current or parent directories.")
+            }
+        },
+    };
+    Ok(ManifestFile::from_path(&manifest_path)?)
+}
+
+/// Find the file within the current directory or parent directories with the given name.
+fn find_file(mut dir: PathBuf, file_name: &str) -> Option<PathBuf> {
+    loop {
+        let path = dir.join(file_name);
+        if path.exists() {
+            return Some(path);
+        }
+        if !dir.pop() {
+            return None;
+        }
+    }
+}
+
+/// Given a `ManifestFile`, return the `ContractABI` of the already compiled contract.
+///
+/// The ABI is expected at `out/<profile>/<package-name>-abi.json`.
+pub fn contract_abi(manifest: &ManifestFile, profile: &str) -> anyhow::Result<ContractABI> {
+    let abi_path = artifact_path(manifest, profile, "-abi")?;
+    pint_abi::from_path(&abi_path).map_err(|err| anyhow::anyhow!("{}", err))
+}
+
+/// Given a `ManifestFile`, return the already compiled contract and its programs.
+///
+/// The contract is expected at `out/<profile>/<package-name>.json`.
+pub async fn contract(
+    manifest: &ManifestFile,
+    profile: &str,
+) -> anyhow::Result<(Contract, Vec<Program>)> {
+    let contract_path = artifact_path(manifest, profile, "")?;
+    contract_from_path(&contract_path).await
+}
+
+/// The path to the package's JSON artifact with the given suffix, checking that it exists.
+fn artifact_path(manifest: &ManifestFile, profile: &str, suffix: &str) -> anyhow::Result<PathBuf> {
+    let profile_dir = manifest.out_dir().join(profile);
+    let path = profile_dir.join(format!("{}{suffix}.json", manifest.pkg.name));
+    if !path.exists() {
+        anyhow::bail!(
+            "Could not find the build output for package `{}` at {:?}. Has it been built with the `{}` profile?",
+            manifest.pkg.name,
+            path,
+            profile
+        );
+    }
+    Ok(path)
+}
@@ -0,0 +1,311 @@
+//! Decoding of queried state into JSON according to the contract's ABI.
+
+use crate::access::{self, ty_name, ty_size, Accessor, StorageAccess};
+use essential_types::{convert::bytes_from_word, Key, Value, Word};
+use pint_abi::types::{ContractABI, TupleField, TypeABI, UnionVariant};
+use serde_json::{Map, Value as Json};
+use std::collections::BTreeMap;
+
+/// Decode every storage variable in the ABI from the given contract state.
+///
+/// The result is an object with an entry per storage variable. Maps are shown as objects
+/// keyed by each entry's map key, written as it would be in a storage access.
+pub fn dump(abi: &ContractABI, state: &BTreeMap<Key, Value>) -> anyhow::Result<Json> {
+    abi.storage
+        .iter()
+        .map(|var| {
+            let access = StorageAccess {
+                var: var.name.clone(),
+                accessors: vec![],
+            };
+            let name = var.name.trim_start_matches("::").to_string();
+            Ok((name, dump_access(abi, &access, &var.ty, state)?))
+        })
+        .collect::<anyhow::Result<Map<_, _>>>()
+        .map(Json::Object)
+}
+
+/// Decode the value of the given type at the given storage access from the contract state.
+fn dump_access(
+    abi: &ContractABI,
+    access: &StorageAccess,
+    ty: &TypeABI,
+    state: &BTreeMap<Key, Value>,
+) -> anyhow::Result<Json> {
+    let with = |accessor| {
+        let mut access = access.clone();
+        access.accessors.push(accessor);
+        access
+    };
+    match ty {
+        TypeABI::Tuple(fields) => {
+            let values = fields
+                .iter()
+                .enumerate()
+                .map(|(ix, field)| {
+                    dump_access(
+                        abi,
+                        &with(Accessor::Field(ix.to_string())),
+                        &field.ty,
+                        state,
+                    )
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(tuple(fields, values))
+        }
+        TypeABI::Array { ty, size } => (0..*size)
+            .map(|ix| dump_access(abi, &with(Accessor::Index(ix.to_string())), ty, state))
+            .collect::<anyhow::Result<_>>()
+            .map(Json::Array),
+        TypeABI::Map { ty_from, ty_to } => {
+            // Each entry's key is the map's prefix followed by the words of the map key.
+            let prefix = access::key_prefix(abi, access)?;
+            let key_len = ty_size(ty_from)?;
+            let mut map_keys: Vec<&[Word]> = state
+                .range(prefix.clone()..)
+                .map(|(key, _)| key)
+                .take_while(|key| key.starts_with(&prefix))
+                .filter_map(|key| key.get(prefix.len()..prefix.len() + key_len))
+                .collect();
+            map_keys.dedup();
+            let mut entries = Map::new();
+            for map_key in map_keys {
+                let text = access::format_value(ty_from, map_key)?;
+                let value = dump_access(abi, &with(Accessor::Index(text.clone())), ty_to, state)?;
+                entries.insert(text, value);
+            }
+            Ok(Json::Object(entries))
+        }
+        ty => {
+            let (key, _) = access::key(abi, access)?;
+            match state.get(&key) {
+                Some(words) if !words.is_empty() => decode(ty, words),
+                _ => Ok(Json::Null),
+            }
+        }
+    }
+}
+
+/// Assemble the typed JSON for a value of the given type from the values of its leaves.
+///
+/// The values must be in the order produced by [`crate::access::leaves`].
+/// Missing values are decoded as `null`.
+pub fn assemble(
+    ty: &TypeABI,
+    values: &mut impl Iterator<Item = Option<Value>>,
+) -> anyhow::Result<Json> {
+    match ty {
+        TypeABI::Tuple(fields) => {
+            let values = fields
+                .iter()
+                .map(|field| assemble(&field.ty, values))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(tuple(fields, values))
+        }
+        TypeABI::Array { ty, size } => (0..*size)
+            .map(|_| assemble(ty, values))
+            .collect::<anyhow::Result<_>>()
+            .map(Json::Array),
+        ty => match values.next().flatten() {
+            Some(words) if !words.is_empty() => decode(ty, &words),
+            _ => Ok(Json::Null),
+        },
+    }
+}
+
+/// Decode the contiguous word encoding of a value of the given type.
+pub fn decode(ty: &TypeABI, words: &[Word]) -> anyhow::Result<Json> {
+    let mut words = words;
+    let json = decode_from(ty, &mut words)?;
+    if !words.is_empty() {
+        anyhow::bail!(
+            "Value has {} more word(s) than expected for a {}",
+            words.len(),
+            ty_name(ty)
+        );
+    }
+    Ok(json)
+}
+
+/// Decode a value of the given type from the front of `words`, advancing past it.
+fn decode_from(ty: &TypeABI, words: &mut &[Word]) -> anyhow::Result<Json> {
+    match ty {
+        TypeABI::Int => Ok(Json::from(take(ty, words, 1)?[0])),
+        TypeABI::Bool => match take(ty, words, 1)?[0] {
+            0 => Ok(Json::Bool(false)),
+            1 => Ok(Json::Bool(true)),
+            w => anyhow::bail!("Invalid bool value {w}"),
+        },
+        TypeABI::Real => Ok(Json::from(f64::from_bits(take(ty, words, 1)?[0] as u64))),
+        TypeABI::B256 => {
+            let bytes: Vec<u8> = take(ty, words, 4)?
+                .iter()
+                .copied()
+                .flat_map(bytes_from_word)
+                .collect();
+            Ok(Json::String(format!("0x{}", hex::encode_upper(bytes))))
+        }
+        TypeABI::Tuple(fields) => {
+            let values = fields
+                .iter()
+                .map(|field| decode_from(&field.ty, words))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(tuple(fields, values))
+        }
+        TypeABI::Array { ty, size } => (0..*size)
+            .map(|_| decode_from(ty, words))
+            .collect::<anyhow::Result<_>>()
+            .map(Json::Array),
+        TypeABI::Union { variants, .. } => {
+            let size = ty_size(ty)?;
+            let union_words = take(ty, words, size)?;
+            let mut payload = &union_words[1..];
+            let variant = usize::try_from(union_words[0])
+                .ok()
+                .and_then(|tag| variants.get(tag))
+                .ok_or_else(|| anyhow::anyhow!("Invalid union tag {}", union_words[0]))?;
+            union(variant, &mut payload)
+        }
+        // Strings and maps have no fixed size, so show the remaining words as is.
+        TypeABI::String | TypeABI::Map { .. } => {
+            let rest = std::mem::take(words);
+            Ok(Json::from(rest.to_vec()))
+        }
+    }
+}
+
+/// A tuple is shown as an object if all of its fields are named, otherwise as an array.
+fn tuple(fields: &[TupleField], values: Vec<Json>) -> Json {
+    if fields.iter().all(|f| f.name.is_some()) {
+        let map: Map<_, _> = fields
+            .iter()
+            .filter_map(|f| f.name.clone())
+            .zip(values)
+            .collect();
+        Json::Object(map)
+    } else {
+        Json::Array(values)
+    }
+}
+
+/// A union variant is shown by name, or as `{ "<name>": <value> }` if it has a value.
+fn union(variant: &UnionVariant, payload: &mut &[Word]) -> anyhow::Result<Json> {
+    let name = variant.name.clone();
+    match &variant.ty {
+        None => Ok(Json::String(name)),
+        Some(ty) => {
+            let value = decode_from(ty, payload)?;
+            Ok(Json::Object(Map::from_iter([(name, value)])))
+        }
+    }
+}
+
+/// Take `n` words from the front of `words`.
+fn take<'a>(ty: &TypeABI, words: &mut &'a [Word], n: usize) -> anyhow::Result<&'a [Word]> {
+    if words.len() < n {
+        anyhow::bail!(
+            "Expected {n} word(s) for a {} but found {}",
+            ty_name(ty),
+            words.len()
+        );
+    }
+    let (taken, rest) = words.split_at(n);
+    *words = rest;
+    Ok(taken)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn decode_primitives() {
+        assert_eq!(decode(&TypeABI::Int, &[-3]).unwrap(), json!(-3));
+        assert_eq!(decode(&TypeABI::Bool, &[1]).unwrap(), json!(true));
+        assert_eq!(
+            decode(&TypeABI::B256, &[0, 0, 0, 0x2a]).unwrap(),
+            json!(format!("0x{}2A", "0".repeat(62)))
+        );
+        assert!(decode(&TypeABI::Int, &[1, 2]).is_err());
+    }
+
+    #[test]
+    fn decode_union() {
+        let ty = TypeABI::Union {
+            name: "Option".to_string(),
+            variants: vec![
+                UnionVariant {
+                    name: "None".to_string(),
+                    ty: None,
+                },
+                UnionVariant {
+                    name: "Some".to_string(),
+                    ty: Some(TypeABI::Int),
+                },
+            ],
+        };
+        assert_eq!(decode(&ty, &[0, 0]).unwrap(), json!("None"));
+        assert_eq!(decode(&ty, &[1, 7]).unwrap(), json!({ "Some": 7 }));
+    }
+
+    #[test]
+    fn dump_map() {
+        let abi = ContractABI {
+            predicates: vec![],
+            storage: vec![
+                pint_abi::types::ParamABI {
+                    name: "::total".to_string(),
+                    ty: TypeABI::Int,
+                },
+                pint_abi::types::ParamABI {
+                    name: "::balances".to_string(),
+                    ty: TypeABI::Map {
+                        ty_from: Box::new(TypeABI::Int),
+                        ty_to: Box::new(TypeABI::Tuple(vec![
+                            TupleField {
+                                name: None,
+                                ty: TypeABI::Int,
+                            },
+                            TupleField {
+                                name: None,
+                                ty: TypeABI::Bool,
+                            },
+                        ])),
+                    },
+                },
+            ],
+        };
+        let state = BTreeMap::from([
+            (vec![1, 3, 0], vec![30]),
+            (vec![1, 3, 1], vec![1]),
+            (vec![1, -2, 0], vec![20]),
+        ]);
+        assert_eq!(
+            dump(&abi, &state).unwrap(),
+            json!({ "total": null, "balances": { "-2": [20, null], "3": [30, true] } })
+        );
+    }
+
+    #[test]
+    fn assemble_tuple() {
+        let ty = TypeABI::Tuple(vec![
+            TupleField {
+                name: Some("count".to_string()),
+                ty: TypeABI::Int,
+            },
+            TupleField {
+                name: Some("flags".to_string()),
+                ty: TypeABI::Array {
+                    ty: Box::new(TypeABI::Bool),
+                    size: 2,
+                },
+            },
+        ]);
+        let values = vec![Some(vec![5]), Some(vec![1]), None];
+        assert_eq!(
+            assemble(&ty, &mut values.into_iter()).unwrap(),
+            json!({ "count": 5, "flags": [true, null] })
+        );
+    }
+}
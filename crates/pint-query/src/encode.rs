@@ -0,0 +1,248 @@
+//! Encoding of typed JSON values into words and state mutations according to the
+//! contract's ABI.
+//!
+//! Values are written as they are shown by [`crate::decode`], so anything printed by a
+//! query can be used as input. A string is parsed as a value written in a storage access,
+//! e.g. `"{1, true}"` or `"Some(42)"`, which is also how values must be written for types
+//! without a JSON form.
+
+use crate::access::{self, names_match, ty_name, ty_size, Accessor, StorageAccess};
+use essential_types::{solution::Mutation, Word};
+use pint_abi::types::{ContractABI, TypeABI};
+use serde_json::Value as Json;
+
+/// Encode the given JSON as a value of the given type.
+pub fn encode(ty: &TypeABI, json: &Json) -> anyhow::Result<Vec<Word>> {
+    let words = match (ty, json) {
+        (ty, Json::String(text)) => access::parse_value(ty, text)?,
+        (TypeABI::Int, Json::Number(n)) => match n.as_i64() {
+            Some(word) => vec![word],
+            None => anyhow::bail!("Invalid int `{n}`"),
+        },
+        (TypeABI::Bool, Json::Bool(b)) => vec![Word::from(*b)],
+        (TypeABI::Real, Json::Number(n)) => match n.as_f64() {
+            Some(real) => vec![real.to_bits() as Word],
+            None => anyhow::bail!("Invalid real `{n}`"),
+        },
+        (TypeABI::Tuple(fields), Json::Array(values)) => {
+            if values.len() != fields.len() {
+                anyhow::bail!(
+                    "Expected a tuple of {} field(s) but found {}",
+                    fields.len(),
+                    values.len()
+                );
+            }
+            let mut words = vec![];
+            for (field, value) in fields.iter().zip(values) {
+                words.extend(encode(&field.ty, value)?);
+            }
+            words
+        }
+        (TypeABI::Tuple(fields), Json::Object(values)) => {
+            if let Some(name) = values
+                .keys()
+                .find(|name| !fields.iter().any(|f| f.name.as_ref() == Some(*name)))
+            {
+                anyhow::bail!("Tuple has no field named `{name}`");
+            }
+            let mut words = vec![];
+            for (ix, field) in fields.iter().enumerate() {
+                let name = field.name.clone().unwrap_or_else(|| ix.to_string());
+                let value = values
+                    .get(&name)
+                    .ok_or_else(|| anyhow::anyhow!("Missing tuple field `{name}`"))?;
+                words.extend(encode(&field.ty, value)?);
+            }
+            words
+        }
+        (TypeABI::Array { ty, size }, Json::Array(values)) => {
+            if values.len() != *size as usize {
+                anyhow::bail!(
+                    "Expected an array of {size} element(s) but found {}",
+                    values.len()
+                );
+            }
+            let mut words = vec![];
+            for value in values {
+                words.extend(encode(ty, value)?);
+            }
+            words
+        }
+        (TypeABI::Union { variants, .. }, Json::Object(values)) if values.len() == 1 => {
+            let (name, value) = values.iter().next().expect("checked length");
+            let (tag, variant) = variants
+                .iter()
+                .enumerate()
+                .find(|(_, v)| {
+                    names_match(&v.name, name) || v.name.rsplit("::").next() == Some(name)
+                })
+                .ok_or_else(|| anyhow::anyhow!("Unknown union variant `{name}`"))?;
+            let Some(variant_ty) = &variant.ty else {
+                anyhow::bail!("Union variant `{name}` has no value");
+            };
+            let mut words = vec![tag as Word];
+            words.extend(encode(variant_ty, value)?);
+            words.resize(ty_size(ty)?, 0);
+            words
+        }
+        (ty, json) => anyhow::bail!("Expected a {} but found `{json}`", ty_name(ty)),
+    };
+    Ok(words)
+}
+
+/// The state mutations that set the value of the given storage access to the given JSON.
+///
+/// Maps are given as objects keyed by each entry's map key, written as it would be in a
+/// storage access. Tuples and arrays are split into a mutation per key-addressable leaf.
+/// `null` clears the value, e.g. to remove a map entry.
+pub fn mutations(
+    abi: &ContractABI,
+    access: &StorageAccess,
+    json: &Json,
+) -> anyhow::Result<Vec<Mutation>> {
+    let ty = access::accessed_ty(abi, access)?;
+    let mut mutations = vec![];
+    match ty {
+        TypeABI::Map { .. } => {
+            let Json::Object(entries) = json else {
+                anyhow::bail!(
+                    "`{}` is a map. Expected an object of entries but found `{json}`",
+                    access.var
+                );
+            };
+            for (key, value) in entries {
+                let mut entry = access.clone();
+                entry.accessors.push(Accessor::Index(key.clone()));
+                mutations.extend(self::mutations(abi, &entry, value)?);
+            }
+        }
+        TypeABI::Tuple(_) | TypeABI::Array { .. } => {
+            let leaves = access::leaves(abi, access)?;
+            let words = match json {
+                Json::Null => vec![],
+                json => encode(ty, json)?,
+            };
+            let mut words = &words[..];
+            for leaf in leaves {
+                let (key, leaf_ty) = access::key(abi, &leaf)?;
+                let value = match json {
+                    Json::Null => vec![],
+                    _ => {
+                        let (value, rest) = words.split_at(ty_size(leaf_ty)?);
+                        words = rest;
+                        value.to_vec()
+                    }
+                };
+                mutations.push(Mutation { key, value });
+            }
+        }
+        ty => {
+            let (key, _) = access::key(abi, access)?;
+            let value = match json {
+                Json::Null => vec![],
+                json => encode(ty, json)?,
+            };
+            mutations.push(Mutation { key, value });
+        }
+    }
+    Ok(mutations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::decode;
+    use pint_abi::types::{ParamABI, TupleField, UnionVariant};
+    use serde_json::json;
+
+    #[test]
+    fn encode_decode_round_trip() {
+        let option = TypeABI::Union {
+            name: "Option".to_string(),
+            variants: vec![
+                UnionVariant {
+                    name: "None".to_string(),
+                    ty: None,
+                },
+                UnionVariant {
+                    name: "Some".to_string(),
+                    ty: Some(TypeABI::Int),
+                },
+            ],
+        };
+        let pair = TypeABI::Tuple(vec![
+            TupleField {
+                name: Some("count".to_string()),
+                ty: TypeABI::Int,
+            },
+            TupleField {
+                name: Some("flags".to_string()),
+                ty: TypeABI::Array {
+                    ty: Box::new(TypeABI::Bool),
+                    size: 2,
+                },
+            },
+        ]);
+        let cases = [
+            (&TypeABI::Int, json!(-3)),
+            (&TypeABI::Real, json!(1.5)),
+            (&TypeABI::B256, json!(format!("0x{}2A", "0".repeat(62)))),
+            (&option, json!("None")),
+            (&option, json!({ "Some": 7 })),
+            (&pair, json!({ "count": 5, "flags": [true, false] })),
+        ];
+        for (ty, value) in cases {
+            assert_eq!(decode(ty, &encode(ty, &value).unwrap()).unwrap(), value);
+        }
+        assert_eq!(
+            encode(&pair, &json!("{5, [true, false]}")).unwrap(),
+            [5, 1, 0]
+        );
+        assert!(encode(&pair, &json!({ "count": 5 })).is_err());
+        assert!(encode(&TypeABI::Int, &json!(true)).is_err());
+    }
+
+    #[test]
+    fn map_and_tuple_mutations() {
+        let abi = ContractABI {
+            predicates: vec![],
+            storage: vec![
+                ParamABI {
+                    name: "::balances".to_string(),
+                    ty: TypeABI::Map {
+                        ty_from: Box::new(TypeABI::Int),
+                        ty_to: Box::new(TypeABI::Int),
+                    },
+                },
+                ParamABI {
+                    name: "::pair".to_string(),
+                    ty: TypeABI::Tuple(vec![
+                        TupleField {
+                            name: None,
+                            ty: TypeABI::Int,
+                        },
+                        TupleField {
+                            name: None,
+                            ty: TypeABI::B256,
+                        },
+                    ]),
+                },
+            ],
+        };
+        let mutation = |key: Vec<Word>, value: Vec<Word>| Mutation { key, value };
+        let balances = "balances".parse().unwrap();
+        assert_eq!(
+            mutations(&abi, &balances, &json!({ "3": 30, "4": null })).unwrap(),
+            [mutation(vec![0, 3], vec![30]), mutation(vec![0, 4], vec![])]
+        );
+        let pair = "pair".parse().unwrap();
+        let owner = format!("0x{}01", "0".repeat(62));
+        assert_eq!(
+            mutations(&abi, &pair, &json!([2, owner])).unwrap(),
+            [
+                mutation(vec![1, 0], vec![2]),
+                mutation(vec![1, 1], vec![0, 0, 0, 1])
+            ]
+        );
+    }
+}
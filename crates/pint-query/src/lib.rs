@@ -0,0 +1,6 @@
+//! Tools for working with the state and solutions of pint contracts by way of their ABI.
+
+pub mod access;
+pub mod artifacts;
+pub mod decode;
+pub mod encode;
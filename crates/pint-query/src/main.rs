@@ -1,9 +1,12 @@
-use clap::{builder::styling::Style, Parser};
-use essential_rest_client::node_client::EssentialNodeClient;
+use clap::Parser;
+use essential_rest_client::{deployments::Deployments, exit, node_client::EssentialNodeClient};
 use essential_types::{convert::words_from_hex_str, ContentAddress, Key};
-use pint_abi::types::{ContractABI, TypeABI};
-use pint_manifest::ManifestFile;
-use std::{fs::read_dir, path::PathBuf};
+use pint_abi::types::ContractABI;
+use pint_query::{
+    access::{self, StorageAccess},
+    artifacts, decode,
+};
+use std::{path::PathBuf, process::ExitCode};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -13,33 +16,59 @@ struct Args {
     #[arg(long)]
     node_address: String,
     /// The contract address to query, encoded as hex.
+    ///
+    /// If not provided, the address is read from the `deployments.toml` written by
+    /// `pint-deploy` next to the package manifest.
     #[arg(long)]
-    contract_address: ContentAddress,
+    contract_address: Option<ContentAddress>,
+    /// The network whose deployment of the package to query when no contract address is
+    /// provided.
+    #[arg(long, default_value = "default", conflicts_with = "contract_address")]
+    network: String,
     /// The path to the package manifest.
     ///
     /// If not provided, the current directory is checked and then each parent
     /// recursively until a manifest is found.
     #[arg(long)]
     manifest_path: Option<PathBuf>,
+    /// The build profile whose output directory contains the contract's ABI.
+    #[arg(long, default_value = "debug")]
+    profile: String,
     /// The exact key to query in state, encoded as hex.
     ///
-    /// One and only one of `key` or `<STORAGE_ACCESS>` is expected.
+    /// One and only one of `key`, `<STORAGE_ACCESS>` or `all` is expected.
     #[arg(long, value_parser = words_from_hex_str )]
     key: Option<Key>,
     /// The storage access to query.
     ///
-    /// This is the name of the storage variable in the contract's storage.
+    /// This is the name of the storage variable in the contract's storage, followed by
+    /// any map keys or array indices in brackets and tuple fields after dots,
+    /// e.g. `balances[0x...]`, `config.owner` or `nested[1][true]`.
     ///
-    /// One and only one of `key` or `<STORAGE_ACCESS>` is expected.
+    /// One and only one of `key`, `<STORAGE_ACCESS>` or `all` is expected.
     storage_access: Option<String>,
+    /// Query and decode every storage variable in the contract's ABI, including all
+    /// entries of maps.
+    ///
+    /// If the contract's storage contains a map, or with `raw`, the set keys are found by
+    /// scanning every block for mutations to the contract, so this may be slow against a
+    /// node with a long history. Otherwise each storage key is queried directly.
+    #[arg(long, conflicts_with_all = ["key", "storage_access"])]
+    all: bool,
+    /// Print the raw words of the queried value instead of decoding it with the ABI.
+    ///
+    /// Values queried by `key` are always printed raw. With `all`, every set key is
+    /// printed along with its value.
+    #[arg(long)]
+    raw: bool,
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let args = Args::parse();
-    if let Err(err) = run(args).await {
-        let bold = Style::new().bold();
-        eprintln!("{}Error:{} {err:?}", bold.render(), bold.render_reset());
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => exit::report(&err),
     }
 }
 
@@ -47,96 +76,103 @@ async fn run(args: Args) -> anyhow::Result<()> {
     let Args {
         node_address,
         contract_address,
+        network,
         manifest_path,
+        profile,
         key,
         storage_access,
+        all,
+        raw,
     } = args;
 
-    let query_key = match (key, storage_access) {
+    let contract_address = match contract_address {
+        Some(address) => address,
+        None => deployed_address(manifest_path.clone(), &network).await?,
+    };
+    let node_client = EssentialNodeClient::new(node_address)?;
+    let output = match (key, storage_access) {
+        _ if all && raw => {
+            let state = node_client
+                .query_state_prefix(contract_address.to_owned(), &[])
+                .await?;
+            serde_json::to_value(state)?
+        }
+        _ if all => {
+            let contract_abi = load_contract_abi(manifest_path, &profile)?;
+            let state = match access::storage_keys(&contract_abi)? {
+                // Without maps every key is known, so avoid scanning the chain.
+                Some(keys) => {
+                    let values = node_client
+                        .query_states(contract_address.to_owned(), &keys)
+                        .await?;
+                    keys.into_iter()
+                        .zip(values)
+                        .filter_map(|(key, value)| Some((key, value?)))
+                        .collect()
+                }
+                None => node_client
+                    .query_state_prefix(contract_address.to_owned(), &[])
+                    .await?
+                    .into_iter()
+                    .collect(),
+            };
+            decode::dump(&contract_abi, &state)?
+        }
         (Some(_), Some(_)) => {
             anyhow::bail!("Only one of storage access name and key hex should be provided.")
         }
         (None, Some(storage_access_name)) => {
-            let manifest_path = match manifest_path {
-                Some(path) => path,
-                None => match find_file(std::env::current_dir()?, ManifestFile::FILE_NAME) {
-                    Some(path) => path,
-                    None => anyhow::bail!(
-                        "Pint manifest could not be found in current or parent directories."
-                    ),
-                },
-            };
-            let manifest = ManifestFile::from_path(&manifest_path)?;
-            let contract_abi = get_contract_abi(&manifest)?;
-            get_key_from_abi(&contract_abi, storage_access_name)?
+            let contract_abi = load_contract_abi(manifest_path, &profile)?;
+            let storage_access: StorageAccess = storage_access_name.parse()?;
+            let ty = access::accessed_ty(&contract_abi, &storage_access)?;
+            let keys = access::leaves(&contract_abi, &storage_access)?
+                .iter()
+                .map(|leaf| access::key(&contract_abi, leaf).map(|(key, _)| key))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let mut values = node_client
+                .query_states(contract_address.to_owned(), &keys)
+                .await?;
+            match (raw, values.len()) {
+                (true, 1) => serde_json::to_value(values.pop())?,
+                (true, _) => serde_json::to_value(values)?,
+                (false, _) => decode::assemble(ty, &mut values.into_iter())?,
+            }
+        }
+        (Some(key), None) => {
+            let value = node_client
+                .query_state(contract_address.to_owned(), key)
+                .await?;
+            serde_json::to_value(value)?
+        }
+        (None, None) => {
+            anyhow::bail!("At least one of key name, key hex or --all should be provided.")
         }
-        (Some(key), None) => key,
-        (None, None) => anyhow::bail!("At least one of key name or key hex should be provided."),
     };
-
-    let node_client = EssentialNodeClient::new(node_address)?;
-    let output = node_client
-        .query_state(contract_address.to_owned(), query_key)
-        .await?;
     println!("{}", serde_json::to_string(&output)?);
     Ok(())
 }
 
-/// Find the file within the current directory or parent directories with the given name.
-fn find_file(mut dir: PathBuf, file_name: &str) -> Option<PathBuf> {
-    loop {
-        let path = dir.join(file_name);
-        if path.exists() {
-            return Some(path);
-        }
-        if !dir.pop() {
-            return None;
-        }
-    }
+/// Load the ABI of the contract at the given manifest path, or of the manifest found in
+/// the current or parent directories, as built with the given profile.
+fn load_contract_abi(manifest_path: Option<PathBuf>, profile: &str) -> anyhow::Result<ContractABI> {
+    let manifest = artifacts::load_manifest(manifest_path)?;
+    artifacts::contract_abi(&manifest, profile)
 }
 
-/// Given a `ManifestFile`, return the `ContractABI` of the already compiled contract.
-fn get_contract_abi(manifest: &ManifestFile) -> anyhow::Result<ContractABI> {
-    let out_dir = manifest.out_dir();
-    // FIXME: Remove hardcoding when Pint has different profiles.
-    let profile = "debug";
-    let profile_dir = out_dir.join(profile);
-    for entry in read_dir(profile_dir.clone())? {
-        let entry = entry?;
-        let entry_file_name = entry.file_name();
-        let name = entry_file_name
-            .to_str()
-            .expect("file name should be convertible to string");
-        if name.ends_with("-abi.json") {
-            return pint_abi::from_path(&entry.path()).map_err(|err| anyhow::anyhow!("{}", err));
-        }
+/// The address of the package's contract as recorded by `pint-deploy` for the given network.
+async fn deployed_address(
+    manifest_path: Option<PathBuf>,
+    network: &str,
+) -> anyhow::Result<ContentAddress> {
+    let manifest = artifacts::load_manifest(manifest_path)?;
+    let path = manifest.dir().join(Deployments::FILE_NAME);
+    if !path.exists() {
+        anyhow::bail!(
+            "No contract address was provided and no deployments were found at {path:?}. \
+            Provide `--contract-address` or deploy the package with `pint-deploy`."
+        );
     }
-    Err(anyhow::anyhow!(
-        "Could not find *-abi.json file in {:?}",
-        profile_dir
-    ))
-}
-
-/// Given a `ContractABI` and a key name, return the `Key`.
-fn get_key_from_abi(abi: &ContractABI, key_name: String) -> anyhow::Result<Key> {
-    abi.storage
-        .iter()
-        .enumerate()
-        .find(|(_, storage)| storage.name == key_name)
-        .map(|(index, storage)| {
-            if matches!(
-                storage.ty,
-                TypeABI::Bool | TypeABI::Int | TypeABI::Real | TypeABI::String | TypeABI::B256
-            ) {
-                let key = vec![index.try_into()?];
-                Ok(key)
-            } else {
-                // FIXME: support complex types
-                Err(anyhow::anyhow!(
-            "Querying key of type {:?} with name is not supported. Try providing a key_hex.",
-                    storage.ty
-                ))
-            }
-        })
-        .unwrap_or_else(|| Err(anyhow::anyhow!("Could not find key \"{key_name}\" in ABI")))
+    let deployments = Deployments::from_path(&path).await?;
+    let deployment = deployments.contract(network, &manifest.pkg.name)?;
+    Ok(deployment.address.clone())
 }
@@ -1,25 +1,83 @@
-use clap::Parser;
-use essential_rest_client::builder_client::EssentialBuilderClient;
-use essential_types::solution::SolutionSet;
-use std::path::PathBuf;
+use anyhow::bail;
+use clap::{builder::styling::Style, error::ErrorKind, CommandFactory, Parser};
+use essential_rest_client::{
+    big_bang_or_default,
+    builder_client::{print_progress, EssentialBuilderClient},
+    dry_run::{self, DryRunOutcome},
+    exit::{self, ValidationError},
+    node_client::EssentialNodeClient,
+};
+use essential_signer::Signature;
+use essential_types::{solution::SolutionSet, ContentAddress};
+use essential_wallet::Wallet;
+use pint_pkg::{build::BuiltPkg, manifest::ManifestFile};
+use pint_query::artifacts;
+use spec::SolutionSetSpec;
+use std::{collections::BTreeMap, path::PathBuf, process::ExitCode};
+
+mod spec;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
+#[group(skip)]
 /// Tool to submit a solution to an Essential builder endpoint.
 struct Args {
+    // The package is built to name its predicates with `--spec`, so all args that
+    // `pint build` does are accepted alongside it.
+    #[command(flatten)]
+    build_args: Option<pint_cli::build::Args>,
     /// The endpoint of builder to bind to.
     #[arg(long)]
     builder_address: String,
     /// Path to the solutions file in the form of a JSON-serialized `SolutionSet`.
-    #[arg(long)]
-    solutions: PathBuf,
+    #[arg(long, required_unless_present = "spec")]
+    solutions: Option<PathBuf>,
+    /// Path to a solution set written in terms of the contract's ABI, as TOML or JSON.
+    ///
+    /// Each solution names the predicate to solve, its arguments by parameter name and its
+    /// state mutations by storage access, e.g. `"balances[0x...]" = 5`. These are encoded
+    /// using the ABI and the built contract of the package.
+    #[arg(long, conflicts_with = "solutions")]
+    spec: Option<PathBuf>,
+    /// The build profile whose output directory contains the built contract and its ABI.
+    #[arg(long, default_value = "debug")]
+    profile: String,
+    /// The wallet account used to sign solutions in the `--spec` that have words to `sign`.
+    #[arg(long, requires = "spec")]
+    account: Option<String>,
+    /// Essential wallet directory.
+    /// If not set then a sensible default will be used (like ~/.essential-wallet).
+    #[arg(long, requires = "account")]
+    wallet: Option<PathBuf>,
+    /// Validate the solutions against a local snapshot of the node instead of submitting them.
+    ///
+    /// The snapshot copies every block of the node into memory.
+    #[arg(long, requires = "node_address")]
+    dry_run: bool,
+    /// The endpoint of the node to validate against with `--dry-run`.
+    #[arg(long, requires = "dry_run")]
+    node_address: Option<String>,
+    /// Path to the big bang configuration of the chain as YAML, used with `--dry-run`.
+    ///
+    /// If not specified, the default big bang configuration is used.
+    #[arg(long, requires = "dry_run")]
+    big_bang: Option<PathBuf>,
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let args = Args::parse();
-    if let Err(err) = run(args).await {
-        eprintln!("Command failed because: {}", err);
+    if args.build_args.is_some() && args.spec.is_none() {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "`pint build` arguments can only be used with `--spec`",
+            )
+            .exit();
+    }
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => exit::report(&err),
     }
 }
 
@@ -27,14 +85,104 @@ async fn run(args: Args) -> anyhow::Result<()> {
     let Args {
         builder_address,
         solutions,
+        spec,
+        build_args,
+        profile,
+        account,
+        wallet,
+        dry_run,
+        node_address,
+        big_bang,
     } = args;
 
-    let builder_client = EssentialBuilderClient::new(builder_address)?;
-    let solution_set = serde_json::from_str::<SolutionSet>(&from_file(solutions).await?)?;
+    let builder_client =
+        EssentialBuilderClient::new(builder_address)?.with_progress(print_progress);
+    let solution_set = match (solutions, spec) {
+        (Some(solutions), _) => serde_json::from_str::<SolutionSet>(&from_file(solutions).await?)?,
+        (None, Some(spec)) => {
+            let spec = SolutionSetSpec::from_path(&spec).await?;
+            let (manifest, predicates) =
+                built_predicates(build_args.unwrap_or_else(default_build_args))?;
+            let abi = artifacts::contract_abi(&manifest, &profile)?;
+            let (contract, _) = artifacts::contract(&manifest, &profile).await?;
+            // Only unlock the wallet if there is something to sign.
+            let has_words_to_sign = spec.solutions.iter().any(|s| s.sign.is_some());
+            let mut wallet = match account {
+                Some(_) if has_words_to_sign => {
+                    let pass = rpassword::prompt_password("Enter password to unlock wallet: ")?;
+                    let wallet = match wallet {
+                        Some(path) => Wallet::new(&pass, path)?,
+                        None => Wallet::with_default_path(&pass)?,
+                    };
+                    Some(wallet)
+                }
+                _ => None,
+            };
+            spec.encode(&abi, &contract, &predicates, |words| {
+                let (Some(wallet), Some(account)) = (wallet.as_mut(), account.as_deref()) else {
+                    bail!("The solution set spec has words to sign. Provide the `--account` to sign with")
+                };
+                match wallet.sign_words(words, account)? {
+                    Signature::Secp256k1(sig) => Ok(sig),
+                    _ => bail!("Account `{account}` does not have a secp256k1 key"),
+                }
+            })?
+        }
+        (None, None) => bail!("One of `--solutions` or `--spec` must be provided"),
+    };
+    if let Some(node_address) = node_address.filter(|_| dry_run) {
+        let big_bang = big_bang_or_default(big_bang.as_deref()).await?;
+        let node_client = EssentialNodeClient::new(node_address)?;
+        let solution_set_ca = essential_hash::content_addr(&solution_set);
+        let outcome = dry_run::dry_run(&node_client, &big_bang, solution_set).await?;
+        let bold = Style::new().bold();
+        println!(
+            "   {}Validated{} solution set {}: {}",
+            bold.render(),
+            bold.render_reset(),
+            solution_set_ca,
+            outcome
+        );
+        if let DryRunOutcome::Invalid { .. } = outcome {
+            return Err(ValidationError(format!("Solution set {outcome}")).into());
+        }
+        return Ok(());
+    }
     let _ = builder_client.submit_solution_set(&solution_set).await?;
     Ok(())
 }
 
+/// Build the package, returning its manifest and the content address of each of its
+/// predicates, by name.
+fn built_predicates(
+    build_args: pint_cli::build::Args,
+) -> anyhow::Result<(ManifestFile, BTreeMap<String, ContentAddress>)> {
+    let (plan, built_pkgs) = pint_cli::build::cmd(build_args)?;
+    let Some(&n) = plan.compilation_order().last() else {
+        bail!("No packages were built")
+    };
+    let BuiltPkg::Contract(built) = &built_pkgs[&n] else {
+        bail!("Expected a contract package, but a library was built")
+    };
+    let manifest = plan.manifests()[&plan.graph()[n].id()].clone();
+    let predicates = built
+        .predicate_metadata
+        .iter()
+        .map(|predicate| (predicate.name.clone(), predicate.ca.clone()))
+        .collect();
+    Ok((manifest, predicates))
+}
+
+/// The `pint build` arguments when none are given, i.e. those of a bare `pint build`.
+fn default_build_args() -> pint_cli::build::Args {
+    #[derive(Parser)]
+    struct Build {
+        #[command(flatten)]
+        args: pint_cli::build::Args,
+    }
+    Build::parse_from(["build"]).args
+}
+
 async fn from_file(path: PathBuf) -> anyhow::Result<String> {
     let content = tokio::fs::read_to_string(path).await?;
     Ok(content)
@@ -0,0 +1,590 @@
+//! Solution sets written in terms of a contract's ABI rather than raw words.
+//!
+//! ```toml
+//! [[solutions]]
+//! predicate = "Transfer"
+//!
+//! [solutions.args]
+//! amount = 5
+//! to = "0x..."
+//!
+//! [solutions.mutations]
+//! "balances[0x...]" = 5
+//! ```
+//!
+//! Predicates are named as in the ABI, with or without the leading `::`, and their
+//! addresses are taken from the predicate names reported by the build. Arguments are
+//! given by parameter name and storage mutations by storage access, with values written
+//! as `pint-query` prints them.
+//!
+//! Signed predicates list the words to sign under `sign`. Each is an int word, a `0x`
+//! prefixed hex string of words, or `$<param>` for the encoded value of an argument.
+//! The signature replaces any `"$signature"` in the arguments, e.g.
+//! `auth = { Signed = "$signature" }`.
+
+use anyhow::Context;
+use essential_sign::secp256k1::ecdsa::RecoverableSignature;
+use essential_types::{
+    contract::Contract,
+    convert::{hex_str_from_words, words_from_hex_str},
+    solution::{Solution, SolutionSet},
+    ContentAddress, PredicateAddress, Word,
+};
+use pint_abi::types::{ContractABI, PredicateABI};
+use pint_query::{access::names_match, encode};
+use serde::Deserialize;
+use serde_json::{Map, Value as Json};
+use std::{collections::BTreeMap, path::Path};
+
+/// A solution set to encode using a contract's ABI.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SolutionSetSpec {
+    /// The solutions in the set.
+    pub solutions: Vec<SolutionSpec>,
+}
+
+/// A single solution to encode using a contract's ABI.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SolutionSpec {
+    /// The name of the predicate to solve.
+    pub predicate: String,
+    /// The value of each of the predicate's parameters, by name.
+    #[serde(default)]
+    pub args: Map<String, Json>,
+    /// The new value of each mutated storage access.
+    #[serde(default)]
+    pub mutations: Map<String, Json>,
+    /// The words to sign, whose signature replaces `"$signature"` in the arguments.
+    #[serde(default)]
+    pub sign: Option<Vec<Json>>,
+}
+
+/// The placeholder in arguments for the signature over a solution's `sign` words.
+const SIGNATURE: &str = "$signature";
+
+impl SolutionSetSpec {
+    /// Read a solution set spec from the given path.
+    ///
+    /// Files with a `.toml` extension are read as TOML, and all others as JSON.
+    pub async fn from_path(path: &Path) -> anyhow::Result<Self> {
+        let string = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("failed to read solution set spec from file {path:?}"))?;
+        let spec = match path.extension() {
+            Some(ext) if ext == "toml" => toml::from_str(&string).with_context(|| {
+                format!("failed to parse solution set spec from TOML at {path:?}")
+            })?,
+            _ => serde_json::from_str(&string).with_context(|| {
+                format!("failed to parse solution set spec from JSON at {path:?}")
+            })?,
+        };
+        Ok(spec)
+    }
+
+    /// Encode the spec into a solution set for the given contract, described by the given ABI.
+    ///
+    /// `predicates` holds the content address of each of the contract's predicates, by name.
+    /// Solutions with words to sign are signed with `sign`.
+    pub fn encode(
+        &self,
+        abi: &ContractABI,
+        contract: &Contract,
+        predicates: &BTreeMap<String, ContentAddress>,
+        mut sign: impl FnMut(&[Word]) -> anyhow::Result<RecoverableSignature>,
+    ) -> anyhow::Result<SolutionSet> {
+        let solutions = self
+            .solutions
+            .iter()
+            .enumerate()
+            .map(|(ix, solution)| {
+                solution
+                    .encode(abi, contract, predicates, &mut sign)
+                    .with_context(|| format!("failed to encode solution {ix}"))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(SolutionSet { solutions })
+    }
+}
+
+impl SolutionSpec {
+    /// Encode the spec into a solution for the given contract, described by the given ABI.
+    ///
+    /// If the solution has words to sign, they are signed with `sign`.
+    pub fn encode(
+        &self,
+        abi: &ContractABI,
+        contract: &Contract,
+        predicates: &BTreeMap<String, ContentAddress>,
+        sign: impl FnOnce(&[Word]) -> anyhow::Result<RecoverableSignature>,
+    ) -> anyhow::Result<Solution> {
+        let predicate_abi = abi
+            .predicates
+            .iter()
+            .find(|p| names_match(&p.name, &self.predicate))
+            .ok_or_else(|| anyhow::anyhow!("Contract has no predicate `{}`", self.predicate))?;
+        let predicate = predicates
+            .iter()
+            .find(|(name, _)| names_match(name, &self.predicate))
+            .map(|(_, ca)| ca.clone())
+            .ok_or_else(|| anyhow::anyhow!("The build has no predicate `{}`", self.predicate))?;
+        if !contract
+            .predicates
+            .iter()
+            .any(|p| essential_hash::content_addr(p) == predicate)
+        {
+            anyhow::bail!("The built contract does not match the build. Try rebuilding it");
+        }
+        let predicate_to_solve = PredicateAddress {
+            contract: essential_hash::content_addr(contract),
+            predicate,
+        };
+
+        if let Some(name) = self.args.keys().find(|name| {
+            !predicate_abi
+                .params
+                .iter()
+                .any(|p| names_match(&p.name, name))
+        }) {
+            anyhow::bail!("Predicate `{}` has no parameter `{name}`", self.predicate);
+        }
+        let mut args = predicate_abi
+            .params
+            .iter()
+            .map(|param| {
+                let name = param.name.trim_start_matches("::");
+                let value = self
+                    .args
+                    .iter()
+                    .find(|(arg, _)| names_match(&param.name, arg))
+                    .map(|(_, value)| value.clone())
+                    .ok_or_else(|| anyhow::anyhow!("Missing argument `{name}`"))?;
+                Ok((name, value))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        match &self.sign {
+            Some(data) => {
+                let words = self.words_to_sign(predicate_abi, data)?;
+                // The ABI's signature type is a tuple of two `b256` and an `int`.
+                let sig = essential_sign::encode::signature(&sign(&words)?);
+                let signature = serde_json::json!([
+                    format!("0x{}", hex_str_from_words(&sig[..4])),
+                    format!("0x{}", hex_str_from_words(&sig[4..8])),
+                    sig[8]
+                ]);
+                for (_, value) in &mut args {
+                    splice(value, &signature);
+                }
+            }
+            None => {
+                if let Some((name, _)) = args.iter().find(|(_, value)| has_placeholder(value)) {
+                    anyhow::bail!(
+                        "Argument `{name}` uses `{SIGNATURE}` but the solution has no words to `sign`"
+                    );
+                }
+            }
+        }
+
+        let predicate_data = predicate_abi
+            .params
+            .iter()
+            .zip(&args)
+            .map(|(param, (name, value))| {
+                encode::encode(&param.ty, value)
+                    .with_context(|| format!("invalid argument `{name}`"))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        let mut state_mutations = vec![];
+        for (access, value) in &self.mutations {
+            let mutations = access
+                .parse()
+                .and_then(|access| encode::mutations(abi, &access, value))
+                .with_context(|| format!("invalid mutation of `{access}`"))?;
+            state_mutations.extend(mutations);
+        }
+
+        Ok(Solution {
+            predicate_to_solve,
+            predicate_data,
+            state_mutations,
+        })
+    }
+
+    /// The words to sign, with any arguments referenced by `$<param>` encoded.
+    fn words_to_sign(
+        &self,
+        predicate_abi: &PredicateABI,
+        data: &[Json],
+    ) -> anyhow::Result<Vec<Word>> {
+        let mut words = vec![];
+        for item in data {
+            match item {
+                Json::Number(n) => words.push(
+                    n.as_i64()
+                        .ok_or_else(|| anyhow::anyhow!("Invalid word `{n}` to sign"))?,
+                ),
+                Json::String(s) => match (s.strip_prefix('$'), s.strip_prefix("0x")) {
+                    (Some(name), _) => {
+                        let param = predicate_abi
+                            .params
+                            .iter()
+                            .find(|p| names_match(&p.name, name))
+                            .ok_or_else(|| {
+                                anyhow::anyhow!("Cannot sign unknown argument `{name}`")
+                            })?;
+                        let value = self
+                            .args
+                            .iter()
+                            .find(|(arg, _)| names_match(&param.name, arg))
+                            .map(|(_, value)| value)
+                            .ok_or_else(|| anyhow::anyhow!("Missing argument `{name}`"))?;
+                        if has_placeholder(value) {
+                            anyhow::bail!("Cannot sign argument `{name}` containing the signature");
+                        }
+                        words.extend(encode::encode(&param.ty, value)?);
+                    }
+                    // Each word is 16 hex digits, and any partial word would be dropped.
+                    (None, Some(hex)) => words.extend(
+                        Some(hex)
+                            .filter(|hex| hex.len() % 16 == 0)
+                            .and_then(|hex| words_from_hex_str(hex).ok())
+                            .ok_or_else(|| anyhow::anyhow!("Invalid hex words `{s}` to sign"))?,
+                    ),
+                    (None, None) => anyhow::bail!(
+                        "Expected an int, `0x` prefixed hex or `$<param>` to sign, found `{s}`"
+                    ),
+                },
+                item => anyhow::bail!(
+                    "Expected an int, hex string or `$<param>` to sign, found `{item}`"
+                ),
+            }
+        }
+        Ok(words)
+    }
+}
+
+/// Replace every signature placeholder within the given value with the signature.
+fn splice(value: &mut Json, signature: &Json) {
+    match value {
+        Json::String(s) if s == SIGNATURE => *value = signature.clone(),
+        Json::Array(values) => values.iter_mut().for_each(|v| splice(v, signature)),
+        Json::Object(values) => values.values_mut().for_each(|v| splice(v, signature)),
+        _ => (),
+    }
+}
+
+/// Whether the given value contains a signature placeholder.
+fn has_placeholder(value: &Json) -> bool {
+    match value {
+        Json::String(s) => s == SIGNATURE,
+        Json::Array(values) => values.iter().any(has_placeholder),
+        Json::Object(values) => values.values().any(has_placeholder),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use essential_sign::secp256k1::{Message, Secp256k1, SecretKey};
+    use essential_types::predicate::{Edge, Node, Predicate, Reads};
+    use pint_abi::types::{ParamABI, TupleField, TypeABI, UnionVariant};
+    use serde_json::json;
+    use std::cell::RefCell;
+
+    fn param(name: &str, ty: TypeABI) -> ParamABI {
+        ParamABI {
+            name: name.to_string(),
+            ty,
+        }
+    }
+
+    fn abi() -> ContractABI {
+        ContractABI {
+            predicates: vec![
+                PredicateABI {
+                    name: "::Mint".to_string(),
+                    params: vec![param("::amount", TypeABI::Int)],
+                },
+                PredicateABI {
+                    name: "::Transfer".to_string(),
+                    params: vec![
+                        param("::amount", TypeABI::Int),
+                        param("::to", TypeABI::B256),
+                    ],
+                },
+                PredicateABI {
+                    name: "::Approve".to_string(),
+                    params: vec![param("::amount", TypeABI::Int), param("::auth", auth())],
+                },
+            ],
+            storage: vec![param(
+                "::balances",
+                TypeABI::Map {
+                    ty_from: Box::new(TypeABI::Int),
+                    ty_to: Box::new(TypeABI::Int),
+                },
+            )],
+        }
+    }
+
+    /// A signature, or nothing.
+    fn auth() -> TypeABI {
+        let signature = TypeABI::Tuple(
+            [TypeABI::B256, TypeABI::B256, TypeABI::Int]
+                .into_iter()
+                .map(|ty| TupleField { name: None, ty })
+                .collect(),
+        );
+        TypeABI::Union {
+            name: "::Auth".to_string(),
+            variants: vec![
+                UnionVariant {
+                    name: "::Auth::Signed".to_string(),
+                    ty: Some(signature),
+                },
+                UnionVariant {
+                    name: "::Auth::Unsigned".to_string(),
+                    ty: None,
+                },
+            ],
+        }
+    }
+
+    fn predicate(id: u8) -> Predicate {
+        Predicate {
+            nodes: vec![Node {
+                edge_start: Edge::MAX,
+                program_address: ContentAddress([id; 32]),
+                reads: Reads::Pre,
+            }],
+            edges: vec![],
+        }
+    }
+
+    /// A contract whose predicates are in a different order to the ABI, along with the
+    /// address of each predicate by name.
+    fn contract() -> (Contract, BTreeMap<String, ContentAddress>) {
+        let named = [("::Transfer", 1), ("::Approve", 3), ("::Mint", 2)]
+            .map(|(name, id)| (name.to_string(), predicate(id)));
+        let predicates = named
+            .iter()
+            .map(|(name, predicate)| (name.clone(), essential_hash::content_addr(predicate)))
+            .collect();
+        let contract = Contract {
+            predicates: named.into_iter().map(|(_, predicate)| predicate).collect(),
+            salt: [0; 32],
+        };
+        (contract, predicates)
+    }
+
+    fn solution(predicate: &str, args: Json, mutations: Json) -> SolutionSpec {
+        let Json::Object(args) = args else {
+            panic!("args must be an object")
+        };
+        let Json::Object(mutations) = mutations else {
+            panic!("mutations must be an object")
+        };
+        SolutionSpec {
+            predicate: predicate.to_string(),
+            args,
+            mutations,
+            sign: None,
+        }
+    }
+
+    fn no_signer(_: &[Word]) -> anyhow::Result<RecoverableSignature> {
+        anyhow::bail!("unexpected signing")
+    }
+
+    fn owner() -> String {
+        format!("0x{}01", "0".repeat(62))
+    }
+
+    #[tokio::test]
+    async fn load_toml_and_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml_path = dir.path().join("spec.toml");
+        std::fs::write(
+            &toml_path,
+            r#"
+            [[solutions]]
+            predicate = "Transfer"
+
+            [solutions.args]
+            amount = 5
+
+            [solutions.mutations]
+            "balances[3]" = 30
+            "#,
+        )
+        .unwrap();
+        let json_path = dir.path().join("spec.json");
+        let json = json!({
+            "solutions": [{
+                "predicate": "Transfer",
+                "args": { "amount": 5 },
+                "mutations": { "balances[3]": 30 },
+            }]
+        });
+        std::fs::write(&json_path, json.to_string()).unwrap();
+
+        for path in [toml_path, json_path] {
+            let spec = SolutionSetSpec::from_path(&path).await.unwrap();
+            let [solution] = &spec.solutions[..] else {
+                panic!("expected one solution in {path:?}")
+            };
+            assert_eq!(solution.predicate, "Transfer");
+            assert_eq!(solution.args["amount"], json!(5));
+            assert_eq!(solution.mutations["balances[3]"], json!(30));
+            assert_eq!(solution.sign, None);
+        }
+
+        let bad_path = dir.path().join("bad.json");
+        std::fs::write(&bad_path, r#"{ "solutions": [], "extra": 1 }"#).unwrap();
+        assert!(SolutionSetSpec::from_path(&bad_path).await.is_err());
+    }
+
+    #[test]
+    fn encode_args_and_mutations() {
+        let (contract, predicates) = contract();
+        let spec = SolutionSetSpec {
+            solutions: vec![solution(
+                "Transfer",
+                json!({ "amount": 5, "::to": owner() }),
+                json!({ "balances[3]": 30 }),
+            )],
+        };
+
+        let set = spec
+            .encode(&abi(), &contract, &predicates, no_signer)
+            .unwrap();
+
+        let [solution] = &set.solutions[..] else {
+            panic!("expected one solution")
+        };
+        assert_eq!(
+            solution.predicate_to_solve,
+            PredicateAddress {
+                contract: essential_hash::content_addr(&contract),
+                predicate: predicates["::Transfer"].clone(),
+            }
+        );
+        assert_eq!(solution.predicate_data, vec![vec![5], vec![0, 0, 0, 1]]);
+        assert_eq!(solution.state_mutations.len(), 1);
+        assert_eq!(solution.state_mutations[0].key, vec![0, 3]);
+        assert_eq!(solution.state_mutations[0].value, vec![30]);
+    }
+
+    #[test]
+    fn invalid_solutions() {
+        let (contract, predicates) = contract();
+        let error = |solution: SolutionSpec| {
+            let err = solution
+                .encode(&abi(), &contract, &predicates, no_signer)
+                .unwrap_err();
+            format!("{err:#}")
+        };
+
+        let missing = solution("Transfer", json!({ "amount": 5 }), json!({}));
+        assert!(error(missing).contains("Missing argument `to`"));
+        let unknown = solution(
+            "Transfer",
+            json!({ "amount": 5, "to": owner(), "from": owner() }),
+            json!({}),
+        );
+        assert!(error(unknown).contains("has no parameter `from`"));
+        let invalid = solution("Mint", json!({ "amount": true }), json!({}));
+        assert!(error(invalid).contains("invalid argument `amount`"));
+        let predicate = solution("Burn", json!({}), json!({}));
+        assert!(error(predicate).contains("no predicate `Burn`"));
+        let mutation = solution("Mint", json!({ "amount": 1 }), json!({ "supply": 1 }));
+        assert!(error(mutation).contains("invalid mutation of `supply`"));
+
+        // The build must agree with the built contract.
+        let mint = solution("Mint", json!({ "amount": 1 }), json!({}));
+        let stale = [("::Mint".to_string(), ContentAddress([0; 32]))].into();
+        let err = mint
+            .encode(&abi(), &contract, &stale, no_signer)
+            .unwrap_err();
+        assert!(err.to_string().contains("Try rebuilding it"));
+    }
+
+    fn signed(args: Json, sign: Json) -> SolutionSpec {
+        let Json::Array(sign) = sign else {
+            panic!("sign must be an array")
+        };
+        SolutionSpec {
+            sign: Some(sign),
+            ..solution("Approve", args, json!({}))
+        }
+    }
+
+    fn signature() -> RecoverableSignature {
+        let sk = SecretKey::from_slice(&[7; 32]).unwrap();
+        Secp256k1::new().sign_ecdsa_recoverable(&Message::from_digest([9; 32]), &sk)
+    }
+
+    #[test]
+    fn sign_words_and_splice_signature() {
+        let (contract, predicates) = contract();
+        let spec = signed(
+            json!({ "amount": 7, "auth": { "Signed": "$signature" } }),
+            json!([
+                1,
+                "0x0000000000000002",
+                "$amount",
+                "0x00000000000000030000000000000004"
+            ]),
+        );
+        let signed_words = RefCell::new(vec![]);
+
+        let solution = spec
+            .encode(&abi(), &contract, &predicates, |words| {
+                signed_words.replace(words.to_vec());
+                Ok(signature())
+            })
+            .unwrap();
+
+        assert_eq!(*signed_words.borrow(), vec![1, 2, 7, 3, 4]);
+        let [a0, a1, a2, a3, b0, b1, b2, b3, c] = essential_sign::encode::signature(&signature());
+        let auth_json = json!({
+            "Signed": [
+                format!("0x{}", hex_str_from_words(&[a0, a1, a2, a3])),
+                format!("0x{}", hex_str_from_words(&[b0, b1, b2, b3])),
+                c,
+            ]
+        });
+        let expected = encode::encode(&auth(), &auth_json).unwrap();
+        assert_eq!(solution.predicate_data, vec![vec![7], expected]);
+    }
+
+    #[test]
+    fn invalid_signatures() {
+        let (contract, predicates) = contract();
+        let error = |solution: SolutionSpec| {
+            let err = solution
+                .encode(&abi(), &contract, &predicates, |_| Ok(signature()))
+                .unwrap_err();
+            format!("{err:#}")
+        };
+        let args = || json!({ "amount": 7, "auth": { "Signed": "$signature" } });
+
+        let unsigned = solution("Approve", args(), json!({}));
+        assert!(error(unsigned)
+            .contains("Argument `auth` uses `$signature` but the solution has no words to `sign`"));
+        let unknown = signed(args(), json!(["$owner"]));
+        assert!(error(unknown).contains("Cannot sign unknown argument `owner`"));
+        let circular = signed(args(), json!(["$auth"]));
+        assert!(error(circular).contains("Cannot sign argument `auth` containing the signature"));
+        let hex = signed(args(), json!(["0x12"]));
+        assert!(error(hex).contains("Invalid hex words `0x12` to sign"));
+        let text = signed(args(), json!(["twelve"]));
+        assert!(error(text).contains("found `twelve`"));
+        let real = signed(args(), json!([1.5]));
+        assert!(error(real).contains("Invalid word `1.5` to sign"));
+    }
+}